@@ -0,0 +1,83 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use slip_codec::SlipEncoder;
+use std::hint::black_box;
+use std::io::{BufWriter, Write};
+
+const END: u8 = 0xC0;
+const ESC: u8 = 0xDB;
+const ESC_END: u8 = 0xDC;
+const ESC_ESC: u8 = 0xDD;
+
+/// The original encoder loop that issues one write per input byte
+fn encode_per_byte(buf: &[u8], sink: &mut dyn Write) -> std::io::Result<usize> {
+    let mut len = sink.write(&[END])?;
+
+    for value in buf.iter() {
+        match *value {
+            END => {
+                len += sink.write(&[ESC, ESC_END])?;
+            }
+            ESC => {
+                len += sink.write(&[ESC, ESC_ESC])?;
+            }
+            _ => {
+                len += sink.write(&[*value])?;
+            }
+        }
+    }
+
+    len += sink.write(&[END])?;
+
+    sink.flush()?;
+
+    Ok(len)
+}
+
+/// Generates a pseudo-random payload with roughly one byte in `period`
+/// requiring an escape sequence
+fn payload(len: usize, period: usize) -> Vec<u8> {
+    let mut seed = 0x2545_f491_u32;
+
+    (0..len)
+        .map(|index| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+
+            match index % period {
+                0 if index % (2 * period) == 0 => END,
+                0 => ESC,
+                _ => (seed as u8) & 0x7f,
+            }
+        })
+        .collect()
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+
+    for (len, period) in [(1006, 256), (65536, 256), (65536, 4)] {
+        let input = payload(len, period);
+        let name = format!("{}/{}", len, period);
+
+        group.throughput(Throughput::Bytes(len as u64));
+
+        group.bench_with_input(BenchmarkId::new("per_byte", &name), &input, |b, input| {
+            let mut sink = BufWriter::new(std::io::sink());
+
+            b.iter(|| encode_per_byte(black_box(input), &mut sink).unwrap());
+        });
+
+        group.bench_with_input(BenchmarkId::new("bulk", &name), &input, |b, input| {
+            let mut sink = BufWriter::new(std::io::sink());
+            let mut slip = SlipEncoder::new(true);
+
+            b.iter(|| slip.encode(black_box(input), &mut sink).unwrap());
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
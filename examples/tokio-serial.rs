@@ -84,7 +84,7 @@ async fn run_source(port: AsyncTTYPort) {
     let mut sink = tokio_util::codec::Framed::new(port, SlipCodec::new());
 
     for message in ["foo", "bar", "baz"].iter() {
-        let message = message.to_string().into();
+        let message = message.to_string();
 
         println!("send {:?}", message);
         sink.send(message).await.unwrap();
@@ -0,0 +1,122 @@
+use super::SlipCodec;
+use crate::checksum::Checksum;
+use crate::framing::{ChecksumLayer, FrameLayer};
+use crate::SlipError;
+use bytes::BytesMut;
+use asynchronous_codec::{Decoder, Encoder};
+
+/// SLIP codec that appends a checksum to each frame
+///
+/// Decoded frames are verified and returned without their checksum. Frames
+/// whose checksum does not match, or that are too short to hold one, are
+/// skipped and counted (see [`checksum_errors`]) so that a noisy link does
+/// not end a `Framed` stream.
+///
+/// [`checksum_errors`]: ChecksumCodec::checksum_errors
+#[derive(Debug)]
+pub struct ChecksumCodec<C> {
+    codec: SlipCodec,
+    layer: ChecksumLayer<C>,
+}
+
+impl<C: Checksum> ChecksumCodec<C> {
+    pub fn new(checksum: C) -> Self {
+        Self::with_codec(SlipCodec::new(), checksum)
+    }
+
+    /// Layers the checksum over a configured SLIP codec
+    pub fn with_codec(codec: SlipCodec, checksum: C) -> Self {
+        Self {
+            codec,
+            layer: ChecksumLayer::new(checksum),
+        }
+    }
+
+    pub fn get_ref(&self) -> &SlipCodec {
+        &self.codec
+    }
+
+    pub fn into_inner(self) -> SlipCodec {
+        self.codec
+    }
+
+    /// Returns the number of frames skipped because their checksum did not
+    /// verify
+    pub fn checksum_errors(&self) -> u64 {
+        self.layer.errors()
+    }
+}
+
+impl<C: Checksum> Decoder for ChecksumCodec<C> {
+    type Item = BytesMut;
+    type Error = SlipError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (decoder, _) = self.codec.engine_mut();
+
+        self.layer.decode(decoder, src)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (decoder, _) = self.codec.engine_mut();
+
+        self.layer.decode_eof(decoder, src)
+    }
+}
+
+impl<C: Checksum> Encoder for ChecksumCodec<C> {
+    type Item<'a> = &'a [u8];
+    type Error = SlipError;
+
+    fn encode(&mut self, item: Self::Item<'_>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let (_, encoder) = self.codec.engine_mut();
+
+        self.layer
+            .encode(item, encoder, dst)
+            .map_err(SlipError::ReadError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::{Crc16Ccitt, Crc32};
+    use asynchronous_codec::FramedRead;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use futures::StreamExt;
+
+    #[test]
+    fn round_trip() {
+        const INPUT: [u8; 4] = [0x01, 0xc0, 0xdb, 0x02];
+
+        let mut codec = ChecksumCodec::new(Crc16Ccitt);
+        let mut buf = BytesMut::new();
+        codec.encode(&INPUT, &mut buf).unwrap();
+        assert_eq!(&[0xc0, 0x01, 0xdb, 0xdc, 0xdb, 0xdd, 0x02], &buf[..7]);
+        assert_eq!(&INPUT, &codec.decode(&mut buf).unwrap().unwrap()[..]);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn skip_corrupted_frames() {
+        let mut codec = ChecksumCodec::new(Crc32);
+        let mut buf = BytesMut::new();
+        codec.encode(&[0x01], &mut buf).unwrap();
+        codec.encode(&[0x02], &mut buf).unwrap();
+        buf[1] ^= 0x80;
+        // too short to hold a checksum
+        buf.extend_from_slice(&[0xc0, 0x01, 0x02, 0xc0]);
+        codec.encode(&[0x03], &mut buf).unwrap();
+
+        block_on(async {
+            let mut stream = FramedRead::new(Cursor::new(buf), ChecksumCodec::new(Crc32));
+            let mut frames = Vec::new();
+            while let Some(frame) = stream.next().await {
+                frames.push(frame.unwrap());
+            }
+            assert_eq!(vec![&[0x02][..], &[0x03][..]], frames);
+            assert_eq!(2, stream.decoder().checksum_errors());
+        });
+    }
+}
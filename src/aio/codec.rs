@@ -1,75 +1,120 @@
-use super::{SlipDecoder, SlipEncoder};
-use crate::{SlipError, MAX_PACKET_SIZE};
-use bytes::{Bytes, BytesMut};
+use crate::framing;
+use crate::SlipError;
+use bytes::BytesMut;
 use asynchronous_codec::{Decoder, Encoder};
 
-#[derive(Debug)]
-pub struct SlipCodec {
-    decoder: SlipDecoder,
-    encoder: SlipEncoder,
-}
-
-pub struct SlipCodecBuilder {
-    begin_with_end: bool,
-    capacity: usize,
-}
-
-impl SlipCodec {
-    pub fn new() -> Self {
-        Self {
-            decoder: SlipDecoder::default(),
-            encoder: SlipEncoder::default(),
-        }
-    }
+/// SLIP codec for `asynchronous_codec` framed I/O
+pub type SlipCodec = framing::SlipCodec<BytesMut>;
 
-    pub fn builder() -> SlipCodecBuilder {
-        SlipCodecBuilder {
-            begin_with_end: true,
-            capacity: MAX_PACKET_SIZE,
-        }
-    }
-}
+/// Builder for [`SlipCodec`] configuration
+pub type SlipCodecBuilder = framing::SlipCodecBuilder<SlipCodec>;
 
 impl Decoder for SlipCodec {
     type Item = BytesMut;
     type Error = SlipError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, Self::Error> {
-        self.decoder.decode(src)
+        self.decode_item(src)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode_item_eof(src)
     }
 }
 
 impl Encoder for SlipCodec {
-    type Item<'a> = Bytes;
+    type Item<'a> = &'a [u8];
     type Error = SlipError;
 
     fn encode(&mut self, item: Self::Item<'_>, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        self.encoder.encode(item, dst).map_err(SlipError::ReadError)
+        self.encode_item(item, dst)
     }
 }
 
-impl Default for SlipCodec {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::tests::{decode_table, encode_table, stream_ends_mid_frame, FrontEnd};
+    use asynchronous_codec::{FramedRead, FramedWrite};
+    use bytes::Bytes;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use futures::{SinkExt, StreamExt};
+
+    impl FrontEnd for SlipCodec {
+        fn decode_frame(&mut self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, SlipError> {
+            Decoder::decode(self, src).map(|frame| frame.map(|frame| frame.to_vec()))
+        }
 
-impl SlipCodecBuilder {
-    pub fn begin_with_end(self, begin_with_end: bool) -> Self {
-        Self {
-            begin_with_end,
-            ..self
+        fn decode_frame_eof(&mut self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, SlipError> {
+            Decoder::decode_eof(self, src).map(|frame| frame.map(|frame| frame.to_vec()))
+        }
+
+        fn encode_frame(&mut self, item: &[u8], dst: &mut BytesMut) -> Result<(), SlipError> {
+            Encoder::encode(self, item, dst)
+        }
+
+        fn read_frames(self, input: &'static [u8]) -> (Vec<Vec<u8>>, Option<SlipError>) {
+            let mut stream = FramedRead::new(Cursor::new(input), self);
+            let mut frames = Vec::new();
+
+            block_on(async {
+                while let Some(frame) = stream.next().await {
+                    match frame {
+                        Ok(frame) => frames.push(frame.to_vec()),
+                        Err(e) => return (frames, Some(e)),
+                    }
+                }
+
+                (frames, None)
+            })
         }
     }
 
-    pub fn capacity(self, capacity: usize) -> Self {
-        Self { capacity, ..self }
+    #[test]
+    fn shared_decode_table() {
+        decode_table::<BytesMut>();
     }
 
-    pub fn build(self) -> SlipCodec {
-        SlipCodec {
-            decoder: SlipDecoder::with_capacity(self.capacity),
-            encoder: SlipEncoder::new(self.begin_with_end),
-        }
+    #[test]
+    fn shared_encode_table() {
+        encode_table::<BytesMut>();
+    }
+
+    #[test]
+    fn shared_stream_ends_mid_frame() {
+        stream_ends_mid_frame::<BytesMut>();
+    }
+
+    const PAYLOAD: [u8; 4] = *b"slip";
+    const ENCODED: [u8; 6] = [0xc0, b's', b'l', b'i', b'p', 0xc0];
+
+    fn encode(item: &[u8]) -> BytesMut {
+        let mut buf = BytesMut::new();
+        SlipCodec::new().encode(item, &mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn encode_borrowed_items() {
+        let vec = PAYLOAD.to_vec();
+        let string = String::from("slip");
+        assert_eq!(&ENCODED, &encode(&Bytes::from_static(&PAYLOAD))[..]);
+        assert_eq!(&ENCODED, &encode(&BytesMut::from(&PAYLOAD[..]))[..]);
+        assert_eq!(&ENCODED, &encode(&vec)[..]);
+        assert_eq!(&ENCODED, &encode(&PAYLOAD)[..]);
+        assert_eq!(&ENCODED, &encode("slip".as_bytes())[..]);
+        assert_eq!(&ENCODED, &encode(string.as_bytes())[..]);
+    }
+
+    #[test]
+    fn send_borrowed_items() {
+        block_on(async {
+            let vec = PAYLOAD.to_vec();
+            let mut sink = FramedWrite::new(Vec::new(), SlipCodec::new());
+            sink.send(&vec).await.unwrap();
+            sink.send(&PAYLOAD[..]).await.unwrap();
+            assert_eq!(ENCODED.repeat(2), sink.into_inner());
+        });
     }
 }
@@ -0,0 +1,404 @@
+//! Bridges a SLIP serial line to a Linux TUN interface, replacing `slattach`
+//! and the kernel SLIP driver.
+//!
+//! ```text
+//! slip-tun [--name NAME] [--mtu BYTES] [--baud RATE] [--stats SECONDS] DEVICE
+//! ```
+//!
+//! The interface is created, given the MTU and brought up; addresses and
+//! routes are configured as usual, e.g. with `ip addr add`. The bridge runs
+//! until the serial line closes or SIGINT/SIGTERM is received, then prints
+//! its statistics and removes the interface.
+
+mod tun;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_sink::Sink;
+use slip_codec::tokio::SlipCodec;
+use slip_codec::{SlipError, MAX_PACKET_SIZE};
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::Pin;
+use std::process::ExitCode;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_serial::SerialPortBuilderExt;
+use tokio_util::codec::Framed;
+use tun::{PacketDevice, Tun};
+
+const USAGE: &str = "\
+usage: slip-tun [OPTIONS] DEVICE
+
+Bridges the SLIP serial line or PTY at DEVICE to a TUN interface.
+
+options:
+    --name NAME        interface name (default: kernel chosen tunN)
+    --mtu BYTES        interface MTU and largest accepted frame (default: 1006)
+    --baud RATE        serial line speed (default: 115200)
+    --stats SECONDS    print statistics periodically
+    -h, --help         print this message";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    device: String,
+    name: String,
+    mtu: usize,
+    baud: u32,
+    stats: Option<Duration>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut device = None;
+        let mut options = Self {
+            device: String::new(),
+            name: String::new(),
+            mtu: MAX_PACKET_SIZE,
+            baud: 115_200,
+            stats: None,
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {}", option))
+            };
+
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--name" => options.name = value(&arg)?,
+                "--mtu" => options.mtu = number(&arg, value(&arg)?)?,
+                "--baud" => options.baud = number(&arg, value(&arg)?)?,
+                "--stats" => options.stats = Some(Duration::from_secs(number(&arg, value(&arg)?)?)),
+                _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+                _ if device.is_none() => device = Some(arg),
+                _ => return Err(format!("unexpected argument {}", arg)),
+            }
+        }
+
+        if options.mtu < 68 || options.mtu > usize::from(u16::MAX) {
+            return Err(format!("invalid MTU {}", options.mtu));
+        }
+
+        if options.stats == Some(Duration::ZERO) {
+            return Err("the statistics interval must not be zero".to_string());
+        }
+
+        options.device = device.ok_or_else(|| "missing DEVICE".to_string())?;
+
+        Ok(Some(options))
+    }
+}
+
+fn number<T: std::str::FromStr>(option: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value {} for {}", value, option))
+}
+
+/// Packet counters of the bridge
+#[derive(Debug, Default)]
+struct Counters {
+    /// packets received from the serial line and written to the interface
+    rx_packets: u64,
+    rx_bytes: u64,
+    /// packets rejected by the interface
+    rx_dropped: u64,
+    /// packets read from the interface and sent on the serial line
+    tx_packets: u64,
+    tx_bytes: u64,
+}
+
+fn print_stats(counters: &Counters, codec: &SlipCodec) {
+    let stats = codec.stats();
+
+    eprintln!(
+        "slip-tun: rx {} packets {} bytes ({} dropped, {} framing errors, {} oversized), \
+         tx {} packets {} bytes",
+        counters.rx_packets,
+        counters.rx_bytes,
+        counters.rx_dropped,
+        stats.framing_errors,
+        stats.oversize_drops,
+        counters.tx_packets,
+        counters.tx_bytes
+    );
+}
+
+async fn next_frame<S>(framed: &mut Framed<S, SlipCodec>) -> Option<Result<Bytes, SlipError>>
+where
+    S: AsyncRead + Unpin,
+{
+    poll_fn(|cx| Pin::new(&mut *framed).poll_next(cx)).await
+}
+
+async fn send_frame<S>(framed: &mut Framed<S, SlipCodec>, packet: Bytes) -> Result<(), SlipError>
+where
+    S: AsyncWrite + Unpin,
+{
+    poll_fn(|cx| Sink::<Bytes>::poll_ready(Pin::new(&mut *framed), cx)).await?;
+    Pin::new(&mut *framed).start_send(packet)?;
+    poll_fn(|cx| Sink::<Bytes>::poll_flush(Pin::new(&mut *framed), cx)).await
+}
+
+async fn run(options: Options) -> io::Result<()> {
+    let serial = tokio_serial::new(&options.device, options.baud).open_native_async()?;
+    let tun = Tun::create(&options.name, options.mtu)?;
+    let codec = SlipCodec::builder().capacity(options.mtu).build();
+    let mut framed = Framed::new(serial, codec);
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let shutdown = async {
+        tokio::select! {
+            _ = interrupt.recv() => {}
+            _ = terminate.recv() => {}
+        }
+    };
+
+    eprintln!(
+        "slip-tun: bridging {} to {} (MTU {})",
+        options.device,
+        tun.name(),
+        options.mtu
+    );
+
+    let counters = bridge(&mut framed, &tun, options.mtu, options.stats, shutdown).await?;
+
+    print_stats(&counters, framed.codec());
+
+    Ok(())
+}
+
+/// Forwards packets between the framed serial line and the device until the
+/// line closes or `shutdown` completes
+async fn bridge<S, D>(
+    framed: &mut Framed<S, SlipCodec>,
+    device: &D,
+    mtu: usize,
+    stats: Option<Duration>,
+    shutdown: impl Future<Output = ()>,
+) -> io::Result<Counters>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    D: PacketDevice,
+{
+    let mut counters = Counters::default();
+    let mut buf = vec![0u8; mtu];
+    let mut ticker = stats.map(tokio::time::interval);
+
+    tokio::pin!(shutdown);
+
+    loop {
+        let tick = async {
+            match ticker.as_mut() {
+                Some(ticker) => ticker.tick().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            frame = next_frame(framed) => match frame {
+                Some(Ok(packet)) => match device.send(&packet).await {
+                    Ok(_) => {
+                        counters.rx_packets += 1;
+                        counters.rx_bytes += packet.len() as u64;
+                    }
+                    // not an IP packet the kernel accepts
+                    Err(err) if err.kind() == io::ErrorKind::InvalidInput => {
+                        counters.rx_dropped += 1;
+                    }
+                    Err(err) => return Err(err),
+                },
+                Some(Err(err)) => return Err(err.into()),
+                None => {
+                    eprintln!("slip-tun: serial line closed");
+                    break;
+                }
+            },
+            len = device.recv(&mut buf) => {
+                let packet = Bytes::copy_from_slice(&buf[..len?]);
+
+                counters.tx_packets += 1;
+                counters.tx_bytes += packet.len() as u64;
+
+                send_frame(framed, packet).await?;
+            }
+            _ = tick => print_stats(&counters, framed.codec()),
+            _ = &mut shutdown => break,
+        }
+    }
+
+    Ok(counters)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("slip-tun: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(options).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("slip-tun: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use tokio::sync::{mpsc, Mutex};
+
+    /// In-memory device that only accepts IPv4 and IPv6 packets
+    struct FakeTun {
+        incoming: Mutex<mpsc::UnboundedReceiver<Vec<u8>>>,
+        sent: std::sync::Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl PacketDevice for FakeTun {
+        async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.incoming.lock().await.recv().await {
+                Some(packet) => {
+                    buf[..packet.len()].copy_from_slice(&packet);
+                    Ok(packet.len())
+                }
+                None => std::future::pending().await,
+            }
+        }
+
+        async fn send(&self, packet: &[u8]) -> io::Result<usize> {
+            match packet.first().map(|byte| byte >> 4) {
+                Some(4) | Some(6) => {
+                    self.sent.lock().unwrap().push(packet.to_vec());
+                    Ok(packet.len())
+                }
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "not an IP packet",
+                )),
+            }
+        }
+    }
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn options() {
+        assert_eq!(
+            Ok(Some(Options {
+                device: "/dev/ttyUSB0".to_string(),
+                name: String::new(),
+                mtu: MAX_PACKET_SIZE,
+                baud: 115_200,
+                stats: None,
+            })),
+            parse(&["/dev/ttyUSB0"])
+        );
+        assert_eq!(
+            Ok(Some(Options {
+                device: "/dev/pts/3".to_string(),
+                name: "sl0".to_string(),
+                mtu: 296,
+                baud: 9600,
+                stats: Some(Duration::from_secs(10)),
+            })),
+            parse(&[
+                "--name",
+                "sl0",
+                "--mtu",
+                "296",
+                "/dev/pts/3",
+                "--baud",
+                "9600",
+                "--stats",
+                "10"
+            ])
+        );
+        assert_eq!(Ok(None), parse(&["--help"]));
+    }
+
+    #[tokio::test]
+    async fn bridge_packets() {
+        const FROM_LINE: [u8; 3] = [0x45, 0xc0, 0x01];
+        const FROM_DEVICE: [u8; 4] = [0x60, 0x00, 0xdb, 0x02];
+
+        let (serial, peer) = tokio::io::duplex(4096);
+        let (incoming, rx) = mpsc::unbounded_channel();
+        let device = FakeTun {
+            incoming: Mutex::new(rx),
+            sent: Default::default(),
+        };
+        let mut framed = Framed::new(serial, SlipCodec::new());
+        let mut peer = Framed::new(peer, SlipCodec::new());
+
+        incoming.send(FROM_DEVICE.to_vec()).unwrap();
+
+        let line = async move {
+            peer.send(&FROM_LINE).await.unwrap();
+            peer.send(&[0x01, 0x02]).await.unwrap();
+            assert_eq!(&FROM_DEVICE[..], peer.next().await.unwrap().unwrap());
+            // dropping the peer closes the line
+        };
+        let (counters, ()) = tokio::join!(
+            bridge(
+                &mut framed,
+                &device,
+                MAX_PACKET_SIZE,
+                None,
+                std::future::pending()
+            ),
+            line
+        );
+        let counters = counters.unwrap();
+
+        assert_eq!(vec![FROM_LINE.to_vec()], *device.sent.lock().unwrap());
+        assert_eq!(1, counters.rx_packets);
+        assert_eq!(FROM_LINE.len() as u64, counters.rx_bytes);
+        assert_eq!(1, counters.rx_dropped);
+        assert_eq!(1, counters.tx_packets);
+        assert_eq!(FROM_DEVICE.len() as u64, counters.tx_bytes);
+    }
+
+    #[tokio::test]
+    async fn bridge_shutdown() {
+        let (serial, _peer) = tokio::io::duplex(4096);
+        let (_incoming, rx) = mpsc::unbounded_channel();
+        let device = FakeTun {
+            incoming: Mutex::new(rx),
+            sent: Default::default(),
+        };
+        let mut framed = Framed::new(serial, SlipCodec::new());
+
+        let counters = bridge(&mut framed, &device, MAX_PACKET_SIZE, None, async {})
+            .await
+            .unwrap();
+
+        assert_eq!(0, counters.rx_packets + counters.tx_packets);
+    }
+
+    #[test]
+    fn invalid_options() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--mtu"]).is_err());
+        assert!(parse(&["--mtu", "40", "/dev/ttyS0"]).is_err());
+        assert!(parse(&["--baud", "fast", "/dev/ttyS0"]).is_err());
+        assert!(parse(&["--stats", "0", "/dev/ttyS0"]).is_err());
+        assert!(parse(&["--verbose", "/dev/ttyS0"]).is_err());
+        assert!(parse(&["/dev/ttyS0", "/dev/ttyS1"]).is_err());
+    }
+}
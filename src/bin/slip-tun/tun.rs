@@ -0,0 +1,128 @@
+//! Minimal Linux TUN device
+
+use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use tokio::io::unix::AsyncFd;
+
+/// Device exchanging one IP packet per call
+pub trait PacketDevice {
+    /// Reads one packet
+    async fn recv(&self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Writes one packet, failing with `InvalidInput` if it is rejected
+    async fn send(&self, packet: &[u8]) -> io::Result<usize>;
+}
+
+/// TUN interface carrying bare IP packets
+///
+/// The interface is removed when the device is dropped.
+pub struct Tun {
+    fd: AsyncFd<OwnedFd>,
+    name: String,
+}
+
+impl Tun {
+    /// Creates the interface `name`, or a kernel chosen `tunN` when empty,
+    /// sets its MTU and brings it up
+    pub fn create(name: &str, mtu: usize) -> io::Result<Self> {
+        let mut ifr = ifreq(name)?;
+        let fd = cvt(unsafe {
+            libc::open(
+                b"/dev/net/tun\0".as_ptr().cast(),
+                libc::O_RDWR | libc::O_NONBLOCK | libc::O_CLOEXEC,
+            )
+        })?;
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        ifr.ifr_ifru.ifru_flags = (libc::IFF_TUN | libc::IFF_NO_PI) as libc::c_short;
+        cvt(unsafe { libc::ioctl(fd.as_raw_fd(), libc::TUNSETIFF, &mut ifr) })?;
+
+        let name = unsafe { CStr::from_ptr(ifr.ifr_name.as_ptr()) }
+            .to_string_lossy()
+            .into_owned();
+        // SAFETY: the descriptor is owned by the `AsyncFd` and stays open
+        // until it is dropped
+        let fd = unsafe { AsyncFd::register(fd) }.map_err(|err| err.into_parts().1)?;
+        let tun = Self { fd, name };
+
+        tun.configure(mtu)?;
+
+        Ok(tun)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn configure(&self, mtu: usize) -> io::Result<()> {
+        let socket = cvt(unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) })?;
+        let socket = unsafe { OwnedFd::from_raw_fd(socket) };
+        let mut ifr = ifreq(&self.name)?;
+
+        ifr.ifr_ifru.ifru_mtu = libc::c_int::try_from(mtu)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "MTU is too large"))?;
+        ioctl(&socket, libc::SIOCSIFMTU, &mut ifr)?;
+        ioctl(&socket, libc::SIOCGIFFLAGS, &mut ifr)?;
+        unsafe { ifr.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short };
+        ioctl(&socket, libc::SIOCSIFFLAGS, &mut ifr)
+    }
+}
+
+impl PacketDevice for Tun {
+    async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fd
+            .async_io(tokio::io::Interest::READABLE, |fd| {
+                cvt_size(unsafe { libc::read(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) })
+            })
+            .await
+    }
+
+    async fn send(&self, packet: &[u8]) -> io::Result<usize> {
+        self.fd
+            .async_io(tokio::io::Interest::WRITABLE, |fd| {
+                cvt_size(unsafe {
+                    libc::write(fd.as_raw_fd(), packet.as_ptr().cast(), packet.len())
+                })
+            })
+            .await
+    }
+}
+
+fn ifreq(name: &str) -> io::Result<libc::ifreq> {
+    let mut ifr: libc::ifreq = unsafe { std::mem::zeroed() };
+
+    if name.len() >= ifr.ifr_name.len() || name.contains('\0') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid interface name",
+        ));
+    }
+
+    for (dst, src) in ifr.ifr_name.iter_mut().zip(name.bytes()) {
+        *dst = src as libc::c_char;
+    }
+
+    Ok(ifr)
+}
+
+fn ioctl(fd: &OwnedFd, request: libc::c_ulong, ifr: &mut libc::ifreq) -> io::Result<()> {
+    cvt(unsafe { libc::ioctl(fd.as_raw_fd(), request as _, ifr as *mut libc::ifreq) }).map(|_| ())
+}
+
+fn cvt(result: RawFd) -> io::Result<RawFd> {
+    if result < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+fn cvt_size(result: libc::ssize_t) -> io::Result<usize> {
+    if result < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result as usize)
+    }
+}
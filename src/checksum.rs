@@ -0,0 +1,403 @@
+//! Checksum layer for SLIP frames
+//!
+//! RFC 1055 does not detect transmission errors. With the `checksum`
+//! feature, a [`Checksum`] is appended to the payload of each frame when it
+//! is encoded and verified and stripped again when the frame is decoded.
+//! A frame whose checksum does not match is reported as
+//! [`SlipError::ChecksumMismatch`].
+//!
+//! [`ChecksumEncoder`] and [`ChecksumDecoder`] wrap the synchronous
+//! [`SlipEncoder`] and [`SlipDecoder`], while `tokio::ChecksumCodec` and
+//! `aio::ChecksumCodec` layer a checksum over the asynchronous codecs. The
+//! codecs skip and count bad frames instead of ending the stream.
+
+use crate::SlipError;
+#[cfg(feature = "std")]
+use crate::{SlipDecoder, SlipEncoder, SlipResult};
+use core::marker::PhantomData;
+
+/// A checksum appended to the payload of each frame
+///
+/// Implementations only need to compute the checksum value; the trailer is
+/// transmitted most significant byte first unless the byte order methods are
+/// overridden.
+pub trait Checksum {
+    /// Length of the checksum trailer in bytes, at most 4 (checked at
+    /// compile time)
+    const LEN: usize;
+
+    /// Computes the checksum of `data`
+    fn checksum(&self, data: &[u8]) -> u32;
+
+    /// Writes `value` into the `LEN` byte `trailer`
+    fn write_trailer(&self, value: u32, trailer: &mut [u8]) {
+        trailer.copy_from_slice(&value.to_be_bytes()[4 - TrailerLen::<Self>::LEN..]);
+    }
+
+    /// Reads the checksum value from the `LEN` byte `trailer`
+    fn read_trailer(&self, trailer: &[u8]) -> u32 {
+        trailer
+            .iter()
+            .fold(0u32, |value, byte| (value << 8) | u32::from(*byte))
+    }
+
+    /// Returns the trailer to append to `payload`, padded to 4 bytes. Only
+    /// the first `LEN` bytes are part of the trailer.
+    fn trailer(&self, payload: &[u8]) -> [u8; 4] {
+        let mut trailer = [0u8; 4];
+
+        self.write_trailer(
+            self.checksum(payload),
+            &mut trailer[..TrailerLen::<Self>::LEN],
+        );
+
+        trailer
+    }
+
+    /// Verifies the checksum at the end of a decoded `frame` and returns the
+    /// length of the payload that precedes it.
+    ///
+    /// A frame too short to hold a checksum is reported as
+    /// [`SlipError::Truncated`].
+    fn verify(&self, frame: &[u8]) -> Result<usize, SlipError> {
+        let len = frame
+            .len()
+            .checked_sub(TrailerLen::<Self>::LEN)
+            .ok_or(SlipError::Truncated { len: frame.len() })?;
+        let (payload, trailer) = frame.split_at(len);
+        let received = self.read_trailer(trailer);
+        let computed = self.checksum(payload);
+
+        if received == computed {
+            Ok(len)
+        } else {
+            Err(SlipError::ChecksumMismatch { received, computed })
+        }
+    }
+}
+
+/// Length of the trailer of `C`, rejecting trailers longer than 4 bytes when
+/// the checksum is used
+struct TrailerLen<C: ?Sized>(PhantomData<C>);
+
+impl<C: Checksum + ?Sized> TrailerLen<C> {
+    const LEN: usize = {
+        assert!(C::LEN <= 4, "checksum trailers are at most 4 bytes long");
+        C::LEN
+    };
+}
+
+const CRC_16_CCITT: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_IBM_3740);
+const CRC_16_MODBUS: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_MODBUS);
+const CRC_32: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+/// CRC-16/CCITT-FALSE (polynomial 0x1021, initial value 0xFFFF), transmitted
+/// most significant byte first
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Crc16Ccitt;
+
+impl Checksum for Crc16Ccitt {
+    const LEN: usize = 2;
+
+    fn checksum(&self, data: &[u8]) -> u32 {
+        u32::from(CRC_16_CCITT.checksum(data))
+    }
+}
+
+/// CRC-16/MODBUS, transmitted least significant byte first as on a Modbus
+/// serial line
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Crc16Modbus;
+
+impl Checksum for Crc16Modbus {
+    const LEN: usize = 2;
+
+    fn checksum(&self, data: &[u8]) -> u32 {
+        u32::from(CRC_16_MODBUS.checksum(data))
+    }
+
+    fn write_trailer(&self, value: u32, trailer: &mut [u8]) {
+        trailer.copy_from_slice(&value.to_le_bytes()[..2]);
+    }
+
+    fn read_trailer(&self, trailer: &[u8]) -> u32 {
+        u32::from(u16::from_le_bytes([trailer[0], trailer[1]]))
+    }
+}
+
+/// CRC-32 as used by Ethernet and zlib, transmitted least significant byte
+/// first
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Crc32;
+
+impl Checksum for Crc32 {
+    const LEN: usize = 4;
+
+    fn checksum(&self, data: &[u8]) -> u32 {
+        CRC_32.checksum(data)
+    }
+
+    fn write_trailer(&self, value: u32, trailer: &mut [u8]) {
+        trailer.copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn read_trailer(&self, trailer: &[u8]) -> u32 {
+        u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]])
+    }
+}
+
+impl Crc32 {
+    /// Starts an incremental CRC-32 over data arriving in pieces
+    #[cfg(feature = "nrf_dfu")]
+    pub(crate) fn digest(&self) -> crc::Digest<'static, u32> {
+        CRC_32.digest()
+    }
+}
+
+/// SLIP encoder that appends a checksum to each frame
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ChecksumEncoder<C> {
+    encoder: SlipEncoder,
+    checksum: C,
+}
+
+#[cfg(feature = "std")]
+impl<C: Checksum> ChecksumEncoder<C> {
+    pub fn new(encoder: SlipEncoder, checksum: C) -> Self {
+        Self { encoder, checksum }
+    }
+
+    /// Encodes `buf` followed by its checksum in a SLIP frame and forwards
+    /// it to the sink.
+    ///
+    /// Returns the number of bytes written to the sink.
+    pub fn encode(&mut self, buf: &[u8], sink: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let trailer = self.checksum.trailer(buf);
+
+        self.encoder.encode_parts(&[buf, &trailer[..C::LEN]], sink)
+    }
+
+    pub fn into_inner(self) -> SlipEncoder {
+        self.encoder
+    }
+}
+
+/// SLIP decoder that verifies and strips the checksum of each frame
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ChecksumDecoder<C> {
+    decoder: SlipDecoder,
+    checksum: C,
+    /// bytes of the current frame appended by calls that would block
+    partial: usize,
+}
+
+#[cfg(feature = "std")]
+impl<C: Checksum> ChecksumDecoder<C> {
+    pub fn new(decoder: SlipDecoder, checksum: C) -> Self {
+        Self {
+            decoder,
+            checksum,
+            partial: 0usize,
+        }
+    }
+
+    /// Decodes a single SLIP frame from the given source and appends its
+    /// payload to `buf`.
+    ///
+    /// Returns the length of the payload. If the checksum does not match,
+    /// [`SlipError::ChecksumMismatch`] is returned; decoding may continue
+    /// with the next frame. On any error other than
+    /// [`SlipError::WouldBlock`] nothing is appended to `buf`.
+    pub fn decode(&mut self, source: &mut dyn std::io::Read, buf: &mut Vec<u8>) -> SlipResult {
+        // the frame may have been started by calls that returned
+        // `SlipError::WouldBlock`
+        let start = buf.len() - self.partial;
+
+        self.partial = 0usize;
+
+        match self.decoder.decode(source, buf) {
+            Ok(_) => {}
+            Err(SlipError::WouldBlock { len }) => {
+                self.partial = len;
+
+                return Err(SlipError::WouldBlock { len });
+            }
+            Err(err) => {
+                buf.truncate(start);
+
+                return Err(err);
+            }
+        }
+
+        match self.checksum.verify(&buf[start..]) {
+            Ok(len) => {
+                buf.truncate(start + len);
+
+                Ok(len)
+            }
+            Err(err) => {
+                buf.truncate(start);
+
+                Err(err)
+            }
+        }
+    }
+
+    pub fn into_inner(self) -> SlipDecoder {
+        self.decoder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECK: &[u8] = b"123456789";
+
+    /// One byte sum of all payload bytes
+    struct Sum8;
+
+    impl Checksum for Sum8 {
+        const LEN: usize = 1;
+
+        fn checksum(&self, data: &[u8]) -> u32 {
+            u32::from(data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)))
+        }
+    }
+
+    #[test]
+    fn check_values() {
+        assert_eq!(0x29b1, Crc16Ccitt.checksum(CHECK));
+        assert_eq!([0x29, 0xb1], Crc16Ccitt.trailer(CHECK)[..2]);
+        assert_eq!(0x4b37, Crc16Modbus.checksum(CHECK));
+        assert_eq!([0x37, 0x4b], Crc16Modbus.trailer(CHECK)[..2]);
+        assert_eq!(0xcbf4_3926, Crc32.checksum(CHECK));
+        assert_eq!([0x26, 0x39, 0xf4, 0xcb], Crc32.trailer(CHECK));
+        assert_eq!(0xdd, Sum8.checksum(CHECK));
+        assert_eq!(0xdd, Sum8.trailer(CHECK)[0]);
+    }
+
+    #[test]
+    fn verify() {
+        let mut frame = CHECK.to_vec();
+        frame.extend_from_slice(&Crc32.trailer(CHECK));
+        assert_eq!(CHECK.len(), Crc32.verify(&frame).unwrap());
+
+        frame[0] ^= 0x01;
+        assert!(matches!(
+            Crc32.verify(&frame),
+            Err(SlipError::ChecksumMismatch {
+                received: 0xcbf4_3926,
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            Crc32.verify(&frame[..3]),
+            Err(SlipError::Truncated { len: 3 })
+        ));
+    }
+
+    #[test]
+    fn encode_decode() {
+        const INPUT: [u8; 4] = [0x01, 0xc0, 0xdb, 0x02];
+
+        let mut encoder = ChecksumEncoder::new(SlipEncoder::default(), Crc16Modbus);
+        let mut output = Vec::new();
+        let second = encoder.encode(&INPUT, &mut output).unwrap();
+        assert_eq!([0xc0, 0x01, 0xdb, 0xdc, 0xdb, 0xdd, 0x02], output[..7]);
+        encoder.encode(&INPUT, &mut output).unwrap();
+        encoder.encode(&[], &mut output).unwrap();
+
+        // corrupt the payload of the second frame
+        output[second + 1] ^= 0x01;
+
+        let mut decoder = ChecksumDecoder::new(SlipDecoder::new(), Crc16Modbus);
+        let mut source = output.as_slice();
+        let mut buf = Vec::new();
+        assert_eq!(4, decoder.decode(&mut source, &mut buf).unwrap());
+        assert_eq!(&INPUT, buf.as_slice());
+        assert!(matches!(
+            decoder.decode(&mut source, &mut buf),
+            Err(SlipError::ChecksumMismatch { .. })
+        ));
+        assert_eq!(4, buf.len());
+        assert_eq!(0, decoder.decode(&mut source, &mut buf).unwrap());
+        assert_eq!(&INPUT, buf.as_slice());
+    }
+
+    #[test]
+    fn decode_across_would_block() {
+        use crate::test_util::{Script, ScriptedReader};
+        use std::io::ErrorKind;
+
+        let mut source = ScriptedReader(
+            vec![
+                Script::Data(&[0x01, 0x02]),
+                Script::Fail(ErrorKind::WouldBlock),
+                Script::Data(&[0x03, 0xc0]),
+            ]
+            .into(),
+        );
+
+        let mut decoder = ChecksumDecoder::new(SlipDecoder::new(), Sum8);
+        let mut buf = vec![0xff];
+        assert!(matches!(
+            decoder.decode(&mut source, &mut buf),
+            Err(SlipError::WouldBlock { len: 2 })
+        ));
+        assert_eq!(2, decoder.decode(&mut source, &mut buf).unwrap());
+        assert_eq!(vec![0xff, 0x01, 0x02], buf);
+    }
+
+    #[test]
+    fn decode_errors_leave_buffer() {
+        use crate::test_util::{Script, ScriptedReader};
+        use std::io::ErrorKind;
+
+        let mut source = ScriptedReader(
+            vec![
+                Script::Data(&[0xc0, 0x01, 0xdb, 0x02, 0xc0]),
+                Script::Data(&[0xc0, 0x01]),
+                Script::Fail(ErrorKind::WouldBlock),
+                Script::Data(&[0x02, 0x03, 0x04, 0xc0]),
+                Script::Data(&[0xc0, 0x05, 0x05, 0xc0]),
+            ]
+            .into(),
+        );
+
+        let mut decoder = ChecksumDecoder::new(SlipDecoder::with_max_len(3), Sum8);
+        let mut buf = vec![0xff];
+        assert!(matches!(
+            decoder.decode(&mut source, &mut buf),
+            Err(SlipError::InvalidEscape { .. })
+        ));
+        assert_eq!(vec![0xff], buf);
+        assert!(matches!(
+            decoder.decode(&mut source, &mut buf),
+            Err(SlipError::WouldBlock { len: 1 })
+        ));
+        assert!(matches!(
+            decoder.decode(&mut source, &mut buf),
+            Err(SlipError::OversizedPacket { limit: 3 })
+        ));
+        assert_eq!(vec![0xff], buf);
+        assert_eq!(1, decoder.decode(&mut source, &mut buf).unwrap());
+        assert_eq!(vec![0xff, 0x05], buf);
+    }
+
+    #[test]
+    fn custom_checksum() {
+        let mut encoder = ChecksumEncoder::new(SlipEncoder::new(false), Sum8);
+        let mut output = Vec::new();
+        encoder.encode(&[0x01, 0x02], &mut output).unwrap();
+        assert_eq!(vec![0x01, 0x02, 0x03, 0xc0], output);
+
+        let mut decoder = ChecksumDecoder::new(SlipDecoder::new(), Sum8);
+        let mut buf = Vec::new();
+        assert_eq!(2, decoder.decode(&mut output.as_slice(), &mut buf).unwrap());
+        assert_eq!(vec![0x01, 0x02], buf);
+    }
+}
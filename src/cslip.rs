@@ -0,0 +1,818 @@
+//! Compressed SLIP (CSLIP)
+//!
+//! [RFC 1144](https://tools.ietf.org/html/rfc1144) Van Jacobson TCP/IP
+//! header compression replaces the 40 byte header of most IPv4 TCP segments
+//! with a few bytes describing what changed since the previous segment of
+//! the same connection. Both ends keep a table of connection slots; the
+//! packet type is carried in the first byte of each frame:
+//!
+//! * `TYPE_IP` — any other IPv4 packet, sent unchanged
+//! * `UNCOMPRESSED_TCP` — a full TCP/IP packet whose protocol field holds
+//!   the slot number, used to (re)synchronise a slot
+//! * `COMPRESSED_TCP` — a change mask, optional slot number, the TCP
+//!   checksum and delta encoded header fields followed by the data
+//!
+//! [`Compressor`] and [`Decompressor`] hold the per-link state.
+//! [`CslipEncoder`] and [`CslipDecoder`] layer them over [`SlipEncoder`] and
+//! [`SlipDecoder`]; `tokio::CslipCodec` and `aio::CslipCodec` do the same for
+//! the asynchronous codecs.
+
+use crate::wire::{header_checksum, read_u16_be, read_u32_be};
+use crate::{SlipDecoder, SlipEncoder, SlipError};
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+/// Default number of connection slots
+pub const DEFAULT_SLOTS: usize = 16;
+
+const TYPE_IP: u8 = 0x40;
+const TYPE_UNCOMPRESSED_TCP: u8 = 0x70;
+const TYPE_COMPRESSED_TCP: u8 = 0x80;
+
+// change mask bits of a compressed header
+const NEW_C: u8 = 0x40;
+const NEW_I: u8 = 0x20;
+const TCP_PUSH_BIT: u8 = 0x10;
+const NEW_S: u8 = 0x08;
+const NEW_A: u8 = 0x04;
+const NEW_W: u8 = 0x02;
+const NEW_U: u8 = 0x01;
+
+// change masks that cannot occur naturally, used to encode common cases
+const SPECIAL_I: u8 = NEW_S | NEW_W | NEW_U;
+const SPECIAL_D: u8 = NEW_S | NEW_A | NEW_W | NEW_U;
+const SPECIALS_MASK: u8 = SPECIAL_D;
+
+// TCP flags
+const TH_FIN: u8 = 0x01;
+const TH_SYN: u8 = 0x02;
+const TH_RST: u8 = 0x04;
+const TH_PUSH: u8 = 0x08;
+const TH_ACK: u8 = 0x10;
+const TH_URG: u8 = 0x20;
+
+const IPPROTO_TCP: u8 = 6;
+
+/// Kind of CSLIP packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketType {
+    /// Any other IPv4 packet, carried unchanged
+    Ip,
+    /// A full TCP/IP packet that (re)synchronises a connection slot
+    UncompressedTcp,
+    /// A TCP/IP packet with a compressed header
+    CompressedTcp,
+}
+
+/// CSLIP error type
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CslipError {
+    /// The SLIP frame could not be decoded
+    Slip(SlipError),
+    /// The `len` byte packet is truncated or its headers are inconsistent
+    Malformed { len: usize },
+    /// The first byte of the packet does not name a known packet type
+    UnknownType(u8),
+    /// The packet refers to a connection slot that does not exist or was
+    /// never initialised
+    Connection(u8),
+    /// A compressed packet was discarded because an earlier error left the
+    /// connection state out of sync
+    Tossed,
+}
+
+impl core::fmt::Display for CslipError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CslipError::Slip(err) => write!(f, "{}", err),
+            CslipError::Malformed { len } => write!(f, "malformed {} byte packet", len),
+            CslipError::UnknownType(value) => write!(f, "unknown packet type 0x{:02X}", value),
+            CslipError::Connection(slot) => write!(f, "invalid connection slot {}", slot),
+            CslipError::Tossed => write!(f, "packet discarded until the next resync"),
+        }
+    }
+}
+
+impl std::error::Error for CslipError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CslipError::Slip(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SlipError> for CslipError {
+    fn from(err: SlipError) -> Self {
+        CslipError::Slip(err)
+    }
+}
+
+/// Saved TCP/IP header of a connection
+#[derive(Debug, Clone, Default)]
+struct Slot {
+    header: Vec<u8>,
+    last_used: u64,
+}
+
+/// Compression state of the sending end of a link
+#[derive(Debug)]
+pub struct Compressor {
+    slots: Vec<Slot>,
+    clock: u64,
+    last_xmit: Option<usize>,
+}
+
+impl Compressor {
+    /// Creates a compressor with `slots` connection slots, between 1 and 256
+    pub fn new(slots: usize) -> Self {
+        assert!((1..=256).contains(&slots), "invalid number of slots");
+
+        Self {
+            slots: vec![Slot::default(); slots],
+            clock: 0u64,
+            last_xmit: None,
+        }
+    }
+
+    /// Compresses `packet` into `dst`, replacing its contents, and returns
+    /// the packet type to transmit.
+    ///
+    /// Packets that cannot be compressed, including anything that is not an
+    /// IPv4 TCP segment, are copied unchanged as [`PacketType::Ip`].
+    pub fn compress(&mut self, packet: &[u8], dst: &mut Vec<u8>) -> PacketType {
+        dst.clear();
+
+        let packet_type = self.compress_tcp(packet, dst);
+
+        if packet_type == PacketType::Ip {
+            dst.clear();
+            dst.extend_from_slice(packet);
+        }
+
+        packet_type
+    }
+
+    fn compress_tcp(&mut self, packet: &[u8], dst: &mut Vec<u8>) -> PacketType {
+        let hlen = match tcp_header_len(packet) {
+            Some(hlen) if packet[9] == IPPROTO_TCP => hlen,
+            _ => return PacketType::Ip,
+        };
+        let th = ip_header_len(packet);
+        let flags = packet[th + 13];
+
+        if read_u16_be(packet, 6) & 0x3fff != 0
+            || usize::from(read_u16_be(packet, 2)) != packet.len()
+            || flags & (TH_SYN | TH_FIN | TH_RST | TH_ACK) != TH_ACK
+        {
+            return PacketType::Ip;
+        }
+
+        let header = &packet[..hlen];
+
+        self.clock += 1;
+
+        let id = match self
+            .slots
+            .iter()
+            .position(|slot| same_connection(&slot.header, header))
+        {
+            Some(id) => id,
+            None => {
+                // reuse the least recently used slot
+                let id = (0..self.slots.len())
+                    .min_by_key(|id| self.slots[*id].last_used)
+                    .unwrap_or_default();
+
+                return self.uncompressed(id, packet, dst);
+            }
+        };
+
+        self.slots[id].last_used = self.clock;
+
+        let old = &self.slots[id].header;
+
+        if old.len() != hlen
+            || old[..2] != header[..2]
+            || old[6..10] != header[6..10]
+            || old[20..th] != header[20..th]
+            || old[th + 20..] != header[th + 20..]
+        {
+            return self.uncompressed(id, packet, dst);
+        }
+
+        let mut changes = 0u8;
+        let mut deltas = Vec::with_capacity(15);
+
+        if flags & TH_URG != 0 {
+            encode_z(read_u16_be(header, th + 18), &mut deltas);
+            changes |= NEW_U;
+        } else if read_u16_be(header, th + 18) != read_u16_be(old, th + 18) {
+            return self.uncompressed(id, packet, dst);
+        }
+
+        let delta_w = read_u16_be(header, th + 14).wrapping_sub(read_u16_be(old, th + 14));
+
+        if delta_w != 0 {
+            encode(delta_w, &mut deltas);
+            changes |= NEW_W;
+        }
+
+        let delta_a = read_u32_be(header, th + 8).wrapping_sub(read_u32_be(old, th + 8));
+
+        if delta_a != 0 {
+            if delta_a > 0xffff {
+                return self.uncompressed(id, packet, dst);
+            }
+
+            encode(delta_a as u16, &mut deltas);
+            changes |= NEW_A;
+        }
+
+        let delta_s = read_u32_be(header, th + 4).wrapping_sub(read_u32_be(old, th + 4));
+
+        if delta_s != 0 {
+            if delta_s > 0xffff {
+                return self.uncompressed(id, packet, dst);
+            }
+
+            encode(delta_s as u16, &mut deltas);
+            changes |= NEW_S;
+        }
+
+        let old_len = u32::from(read_u16_be(old, 2));
+        let old_data_len = old_len.wrapping_sub(hlen as u32);
+
+        match changes {
+            // a data segment following a pure ack is sent compressed, but a
+            // retransmission or window probe is sent uncompressed in case
+            // the other side missed the compressed version
+            0 if old_len as usize != hlen || packet.len() == hlen => {
+                return self.uncompressed(id, packet, dst)
+            }
+            SPECIAL_I | SPECIAL_D => return self.uncompressed(id, packet, dst),
+            _ if changes == NEW_S | NEW_A && delta_s == delta_a && delta_s == old_data_len => {
+                changes = SPECIAL_I;
+                deltas.clear();
+            }
+            NEW_S if delta_s == old_data_len => {
+                changes = SPECIAL_D;
+                deltas.clear();
+            }
+            _ => {}
+        }
+
+        let delta_i = read_u16_be(header, 4).wrapping_sub(read_u16_be(old, 4));
+
+        if delta_i != 1 {
+            encode_z(delta_i, &mut deltas);
+            changes |= NEW_I;
+        }
+
+        if flags & TH_PUSH != 0 {
+            changes |= TCP_PUSH_BIT;
+        }
+
+        self.slots[id].header.copy_from_slice(header);
+
+        if self.last_xmit != Some(id) {
+            self.last_xmit = Some(id);
+            dst.push(TYPE_COMPRESSED_TCP | NEW_C | changes);
+            dst.push(id as u8);
+        } else {
+            dst.push(TYPE_COMPRESSED_TCP | changes);
+        }
+
+        dst.extend_from_slice(&header[th + 16..th + 18]);
+        dst.extend_from_slice(&deltas);
+        dst.extend_from_slice(&packet[hlen..]);
+
+        PacketType::CompressedTcp
+    }
+
+    /// Saves the header in slot `id` and sends the whole packet with the slot
+    /// number in place of the protocol
+    fn uncompressed(&mut self, id: usize, packet: &[u8], dst: &mut Vec<u8>) -> PacketType {
+        let hlen = tcp_header_len(packet).unwrap_or(packet.len());
+        let slot = &mut self.slots[id];
+
+        slot.header.clear();
+        slot.header.extend_from_slice(&packet[..hlen]);
+        slot.last_used = self.clock;
+        self.last_xmit = Some(id);
+
+        dst.extend_from_slice(packet);
+        dst[0] = TYPE_UNCOMPRESSED_TCP | (dst[0] & 0x0f);
+        dst[9] = id as u8;
+
+        PacketType::UncompressedTcp
+    }
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new(DEFAULT_SLOTS)
+    }
+}
+
+/// Decompression state of the receiving end of a link
+#[derive(Debug)]
+pub struct Decompressor {
+    slots: Vec<Vec<u8>>,
+    last_recv: Option<usize>,
+    toss: bool,
+}
+
+impl Decompressor {
+    /// Creates a decompressor with `slots` connection slots, between 1 and
+    /// 256
+    pub fn new(slots: usize) -> Self {
+        assert!((1..=256).contains(&slots), "invalid number of slots");
+
+        Self {
+            slots: vec![Vec::new(); slots],
+            last_recv: None,
+            toss: false,
+        }
+    }
+
+    /// Restores the IP packet carried in `frame` into `dst`, replacing its
+    /// contents, and returns the type of the received packet.
+    ///
+    /// After an error, compressed packets are discarded with
+    /// [`CslipError::Tossed`] until a packet naming its connection slot
+    /// arrives, as the sender's state can no longer be tracked. TCP
+    /// retransmissions resynchronise the link.
+    pub fn decompress(
+        &mut self,
+        frame: &[u8],
+        dst: &mut Vec<u8>,
+    ) -> Result<PacketType, CslipError> {
+        dst.clear();
+
+        let result = match frame.first() {
+            Some(value) if value & TYPE_COMPRESSED_TCP != 0 => self.compressed(frame, dst),
+            Some(value) if value & 0xf0 == TYPE_UNCOMPRESSED_TCP => self.uncompressed(frame, dst),
+            Some(value) if value & 0xf0 == TYPE_IP => {
+                dst.extend_from_slice(frame);
+                Ok(PacketType::Ip)
+            }
+            Some(value) => Err(CslipError::UnknownType(*value)),
+            None => Err(CslipError::Malformed { len: 0 }),
+        };
+
+        if result.is_err() {
+            dst.clear();
+            self.toss = true;
+        }
+
+        result
+    }
+
+    fn uncompressed(&mut self, frame: &[u8], dst: &mut Vec<u8>) -> Result<PacketType, CslipError> {
+        let malformed = CslipError::Malformed { len: frame.len() };
+
+        if frame.len() < 20 {
+            return Err(malformed);
+        }
+
+        let id = frame[9];
+
+        if usize::from(id) >= self.slots.len() {
+            return Err(CslipError::Connection(id));
+        }
+
+        dst.extend_from_slice(frame);
+        dst[0] = TYPE_IP | (dst[0] & 0x0f);
+        dst[9] = IPPROTO_TCP;
+
+        let hlen = tcp_header_len(dst).ok_or(malformed)?;
+        let slot = &mut self.slots[usize::from(id)];
+
+        slot.clear();
+        slot.extend_from_slice(&dst[..hlen]);
+        self.last_recv = Some(usize::from(id));
+        self.toss = false;
+
+        Ok(PacketType::UncompressedTcp)
+    }
+
+    fn compressed(&mut self, frame: &[u8], dst: &mut Vec<u8>) -> Result<PacketType, CslipError> {
+        let malformed = || CslipError::Malformed { len: frame.len() };
+        let changes = frame[0];
+        let mut cursor = 1usize;
+
+        if changes & NEW_C != 0 {
+            let id = *frame.get(1).ok_or_else(malformed)?;
+
+            if usize::from(id) >= self.slots.len() || self.slots[usize::from(id)].is_empty() {
+                return Err(CslipError::Connection(id));
+            }
+
+            self.last_recv = Some(usize::from(id));
+            self.toss = false;
+            cursor += 1;
+        } else if self.toss {
+            return Err(CslipError::Tossed);
+        }
+
+        let id = self.last_recv.ok_or(CslipError::Tossed)?;
+        let header = &mut self.slots[id];
+        let hlen = header.len();
+        let th = ip_header_len(header);
+        let checksum = frame.get(cursor..cursor + 2).ok_or_else(malformed)?;
+
+        header[th + 16..th + 18].copy_from_slice(checksum);
+        cursor += 2;
+
+        if changes & TCP_PUSH_BIT != 0 {
+            header[th + 13] |= TH_PUSH;
+        } else {
+            header[th + 13] &= !TH_PUSH;
+        }
+
+        let data_len = u32::from(read_u16_be(header, 2)).wrapping_sub(hlen as u32);
+
+        match changes & SPECIALS_MASK {
+            SPECIAL_I => {
+                add_u32(header, th + 8, data_len);
+                add_u32(header, th + 4, data_len);
+            }
+            SPECIAL_D => add_u32(header, th + 4, data_len),
+            _ => {
+                if changes & NEW_U != 0 {
+                    header[th + 13] |= TH_URG;
+
+                    let urp = decode(frame, &mut cursor).ok_or_else(malformed)?;
+
+                    header[th + 18..th + 20].copy_from_slice(&urp.to_be_bytes());
+                } else {
+                    header[th + 13] &= !TH_URG;
+                }
+
+                if changes & NEW_W != 0 {
+                    let delta = decode(frame, &mut cursor).ok_or_else(malformed)?;
+                    let window = read_u16_be(header, th + 14).wrapping_add(delta);
+
+                    header[th + 14..th + 16].copy_from_slice(&window.to_be_bytes());
+                }
+
+                if changes & NEW_A != 0 {
+                    let delta = decode(frame, &mut cursor).ok_or_else(malformed)?;
+
+                    add_u32(header, th + 8, u32::from(delta));
+                }
+
+                if changes & NEW_S != 0 {
+                    let delta = decode(frame, &mut cursor).ok_or_else(malformed)?;
+
+                    add_u32(header, th + 4, u32::from(delta));
+                }
+            }
+        }
+
+        let delta_i = if changes & NEW_I != 0 {
+            decode(frame, &mut cursor).ok_or_else(malformed)?
+        } else {
+            1u16
+        };
+        let ip_id = read_u16_be(header, 4).wrapping_add(delta_i);
+        let data = &frame[cursor..];
+        let len = u16::try_from(hlen + data.len()).map_err(|_| malformed())?;
+
+        header[4..6].copy_from_slice(&ip_id.to_be_bytes());
+        header[2..4].copy_from_slice(&len.to_be_bytes());
+        header[10..12].fill(0u8);
+
+        let checksum = header_checksum(&header[..th]);
+
+        header[10..12].copy_from_slice(&checksum.to_be_bytes());
+        dst.extend_from_slice(header);
+        dst.extend_from_slice(data);
+
+        Ok(PacketType::CompressedTcp)
+    }
+}
+
+impl Default for Decompressor {
+    fn default() -> Self {
+        Self::new(DEFAULT_SLOTS)
+    }
+}
+
+/// SLIP encoder that compresses TCP/IP headers
+#[derive(Debug)]
+pub struct CslipEncoder {
+    encoder: SlipEncoder,
+    compressor: Compressor,
+    buf: Vec<u8>,
+}
+
+impl CslipEncoder {
+    /// Creates an encoder that compresses packets before framing them
+    pub fn new(encoder: SlipEncoder, compressor: Compressor) -> Self {
+        Self {
+            encoder,
+            compressor,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Compresses an IP packet and forwards it to the sink in a SLIP frame.
+    ///
+    /// Returns the number of bytes written to the sink.
+    pub fn encode(&mut self, packet: &[u8], sink: &mut dyn Write) -> std::io::Result<usize> {
+        self.compressor.compress(packet, &mut self.buf);
+        self.encoder.encode(&self.buf, sink)
+    }
+}
+
+/// SLIP decoder that restores compressed TCP/IP headers
+#[derive(Debug)]
+pub struct CslipDecoder {
+    decoder: SlipDecoder,
+    decompressor: Decompressor,
+    buf: Vec<u8>,
+}
+
+impl CslipDecoder {
+    /// Creates a decoder that restores packets after unframing them
+    pub fn new(decoder: SlipDecoder, decompressor: Decompressor) -> Self {
+        Self {
+            decoder,
+            decompressor,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Decodes a single SLIP frame from the given source and restores the IP
+    /// packet it carries into `dst`, replacing its contents.
+    ///
+    /// A packet that cannot be restored is reported as an error; decoding
+    /// may continue with the next frame.
+    pub fn decode(
+        &mut self,
+        source: &mut dyn Read,
+        dst: &mut Vec<u8>,
+    ) -> Result<PacketType, CslipError> {
+        let result = match self.decoder.decode(source, &mut self.buf) {
+            // the partial frame is kept until more data is available
+            Err(err @ SlipError::WouldBlock { .. }) => return Err(err.into()),
+            Err(err) => Err(err.into()),
+            Ok(_) => self.decompressor.decompress(&self.buf, dst),
+        };
+
+        self.buf.clear();
+
+        result
+    }
+}
+
+/// Returns the combined IPv4 and TCP header length of a TCP/IP packet
+fn tcp_header_len(packet: &[u8]) -> Option<usize> {
+    if packet.len() < 40 || packet[0] >> 4 != 4 {
+        return None;
+    }
+
+    let th = ip_header_len(packet);
+    let hlen = th + usize::from(packet.get(th + 12)? >> 4) * 4;
+
+    if th < 20 || hlen < th + 20 || hlen > packet.len() {
+        return None;
+    }
+
+    Some(hlen)
+}
+
+fn ip_header_len(packet: &[u8]) -> usize {
+    usize::from(packet[0] & 0x0f) * 4
+}
+
+fn same_connection(a: &[u8], b: &[u8]) -> bool {
+    if a.is_empty() || a[12..20] != b[12..20] {
+        return false;
+    }
+
+    let (ta, tb) = (ip_header_len(a), ip_header_len(b));
+
+    a.get(ta..ta + 4) == b.get(tb..tb + 4)
+}
+
+/// Appends a delta, using three bytes for values of 256 or more
+fn encode(value: u16, buf: &mut Vec<u8>) {
+    if value >= 256 {
+        buf.push(0u8);
+        buf.extend_from_slice(&value.to_be_bytes());
+    } else {
+        buf.push(value as u8);
+    }
+}
+
+/// Appends a delta that may be zero, using three bytes for zero too
+fn encode_z(value: u16, buf: &mut Vec<u8>) {
+    if value == 0 || value >= 256 {
+        buf.push(0u8);
+        buf.extend_from_slice(&value.to_be_bytes());
+    } else {
+        buf.push(value as u8);
+    }
+}
+
+fn decode(frame: &[u8], cursor: &mut usize) -> Option<u16> {
+    match *frame.get(*cursor)? {
+        0 => {
+            let bytes = frame.get(*cursor + 1..*cursor + 3)?;
+
+            *cursor += 3;
+            Some(read_u16_be(bytes, 0))
+        }
+        value => {
+            *cursor += 1;
+            Some(u16::from(value))
+        }
+    }
+}
+
+fn add_u32(buf: &mut [u8], offset: usize, delta: u32) {
+    let value = read_u32_be(buf, offset).wrapping_add(delta);
+
+    buf[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a TCP/IP packet from 10.0.0.1 to 10.0.0.2
+    pub(crate) fn segment(
+        src_port: u16,
+        id: u16,
+        seq: u32,
+        ack: u32,
+        flags: u8,
+        window: u16,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut packet = vec![
+            0x45, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x40, 0x06, 0x00, 0x00, 0x0a, 0x00,
+            0x00, 0x01, 0x0a, 0x00, 0x00, 0x02,
+        ];
+        let len = (40 + data.len()) as u16;
+
+        packet[2..4].copy_from_slice(&len.to_be_bytes());
+        packet[4..6].copy_from_slice(&id.to_be_bytes());
+
+        let checksum = header_checksum(&packet);
+
+        packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+        packet.extend_from_slice(&src_port.to_be_bytes());
+        packet.extend_from_slice(&23u16.to_be_bytes());
+        packet.extend_from_slice(&seq.to_be_bytes());
+        packet.extend_from_slice(&ack.to_be_bytes());
+        packet.extend_from_slice(&[0x50, flags]);
+        packet.extend_from_slice(&window.to_be_bytes());
+        // arbitrary TCP checksum, carried verbatim
+        packet.extend_from_slice(&(id ^ 0x5a5a).to_be_bytes());
+        packet.extend_from_slice(&[0x00, 0x00]);
+        packet.extend_from_slice(data);
+        packet
+    }
+
+    /// A telnet style session: handshake, echoed keystrokes and bulk data
+    pub(crate) fn session() -> Vec<Vec<u8>> {
+        vec![
+            segment(1024, 1, 1000, 0, TH_SYN, 4096, &[]),
+            segment(1024, 2, 1001, 5001, TH_ACK, 4096, &[]),
+            segment(1024, 3, 1001, 5001, TH_ACK | TH_PUSH, 4096, b"l"),
+            segment(1024, 4, 1002, 5002, TH_ACK | TH_PUSH, 4096, b"s"),
+            segment(1024, 5, 1003, 5003, TH_ACK | TH_PUSH, 4096, b"\r"),
+            segment(1024, 6, 1004, 5003, TH_ACK, 4096, b"bulk data one"),
+            segment(1024, 7, 1017, 5003, TH_ACK, 4096, b"bulk data two"),
+            segment(2048, 100, 7000, 9000, TH_ACK, 8192, b"other connection"),
+            segment(1024, 9, 1030, 5400, TH_ACK, 4000, b"more"),
+            segment(1024, 10, 1034, 5400, TH_ACK | TH_URG, 4000, b"!"),
+            segment(1024, 11, 1035, 5400, TH_ACK | TH_FIN, 4000, &[]),
+        ]
+    }
+
+    #[test]
+    fn round_trip() {
+        const TYPES: [PacketType; 11] = [
+            PacketType::Ip,
+            PacketType::UncompressedTcp,
+            PacketType::CompressedTcp,
+            PacketType::CompressedTcp,
+            PacketType::CompressedTcp,
+            PacketType::CompressedTcp,
+            PacketType::CompressedTcp,
+            PacketType::UncompressedTcp,
+            PacketType::CompressedTcp,
+            PacketType::CompressedTcp,
+            PacketType::Ip,
+        ];
+
+        let mut compressor = Compressor::default();
+        let mut decompressor = Decompressor::default();
+        let mut compressed = Vec::new();
+        let mut restored = Vec::new();
+
+        for (packet, expected) in session().iter().zip(TYPES.iter()) {
+            assert_eq!(*expected, compressor.compress(packet, &mut compressed));
+            assert_eq!(
+                *expected,
+                decompressor.decompress(&compressed, &mut restored).unwrap()
+            );
+            assert_eq!(packet, &restored);
+        }
+    }
+
+    #[test]
+    fn compressed_headers() {
+        let session = session();
+        let mut compressor = Compressor::default();
+        let mut buf = Vec::new();
+
+        compressor.compress(&session[1], &mut buf);
+        assert_eq!(0x75, buf[0]);
+        assert_eq!(0x00, buf[9]);
+
+        // data after a pure ack: only the push bit and checksum change
+        compressor.compress(&session[2], &mut buf);
+        assert_eq!(vec![0x90, 0x5a, 0x59, b'l'], buf);
+
+        // echoed keystroke: seq and ack both advance by the last data length
+        compressor.compress(&session[3], &mut buf);
+        assert_eq!(vec![0x9b, 0x5a, 0x5e, b's'], buf);
+
+        compressor.compress(&session[4], &mut buf);
+        assert_eq!(vec![0x9b, 0x5a, 0x5f, b'\r'], buf);
+
+        // unidirectional data: seq advances by the last data length
+        compressor.compress(&session[5], &mut buf);
+        assert_eq!(&[0x8f, 0x5a, 0x5c], &buf[..3]);
+
+        compressor.compress(&session[6], &mut buf);
+        assert_eq!(&[0x8f, 0x5a, 0x5d], &buf[..3]);
+
+        compressor.compress(&session[7], &mut buf);
+        assert_eq!(0x01, buf[9]);
+
+        // back to the first connection: window, ack, seq and id deltas
+        compressor.compress(&session[8], &mut buf);
+        assert_eq!(
+            &[0xee, 0x00, 0x5a, 0x53, 0x00, 0xff, 0xa0, 0x00, 0x01, 0x8d, 0x0d, 0x02],
+            &buf[..12]
+        );
+    }
+
+    #[test]
+    fn toss_until_resync() {
+        let session = session();
+        let mut compressor = Compressor::default();
+        let mut decompressor = Decompressor::default();
+        let mut compressed = Vec::new();
+        let mut restored = Vec::new();
+
+        for packet in session[..3].iter() {
+            compressor.compress(packet, &mut compressed);
+            decompressor.decompress(&compressed, &mut restored).unwrap();
+        }
+
+        assert!(matches!(
+            decompressor.decompress(&[0x20, 0x00], &mut restored),
+            Err(CslipError::UnknownType(0x20))
+        ));
+
+        compressor.compress(&session[3], &mut compressed);
+        assert!(matches!(
+            decompressor.decompress(&compressed, &mut restored),
+            Err(CslipError::Tossed)
+        ));
+        assert!(restored.is_empty());
+
+        // a packet for another connection names its slot and resyncs
+        compressor.compress(&session[7], &mut compressed);
+        decompressor.decompress(&compressed, &mut restored).unwrap();
+        assert_eq!(session[7], restored);
+    }
+
+    #[test]
+    fn encode_decode() {
+        let mut encoder = CslipEncoder::new(SlipEncoder::default(), Compressor::default());
+        let mut output = Vec::new();
+
+        for packet in session().iter() {
+            encoder.encode(packet, &mut output).unwrap();
+        }
+
+        let mut decoder = CslipDecoder::new(SlipDecoder::new(), Decompressor::default());
+        let mut source = output.as_slice();
+        let mut packet = Vec::new();
+
+        for expected in session().iter() {
+            decoder.decode(&mut source, &mut packet).unwrap();
+            assert_eq!(expected, &packet);
+        }
+    }
+}
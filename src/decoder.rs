@@ -1,74 +1,553 @@
-use crate::{END, ESC, ESC_END, ESC_ESC};
+use crate::{SlipStats, END, ESC, ESC_END, ESC_ESC};
+#[cfg(feature = "std")]
 use std::io::{Read, Write};
 
 /// SLIP decoder error type
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum SlipError {
-    FramingError,
-    OversizedPacket,
+    /// An `ESC` token was followed by `byte` instead of `ESC_END` or
+    /// `ESC_ESC`. The `offset` of the offending byte is counted from the
+    /// first byte seen by the decoder.
+    InvalidEscape { byte: u8, offset: u64 },
+    /// A frame did not fit in the `limit` bytes available to hold it
+    OversizedPacket { limit: usize },
+    /// The data ended in the middle of a frame after `len` decoded bytes,
+    /// or a `len` byte frame was too short to hold its checksum
+    Truncated { len: usize },
+    /// The checksum `received` at the end of a frame does not match the one
+    /// `computed` over its payload
+    #[cfg(feature = "checksum")]
+    ChecksumMismatch { received: u32, computed: u32 },
+    /// The data ended before a complete frame was decoded
     EndOfStream,
+    /// The source had no data available after `len` decoded bytes of a
+    /// frame. The partial frame is kept and decoding resumes with the next
+    /// call once more data is available.
+    #[cfg(feature = "std")]
+    WouldBlock { len: usize },
+    #[cfg(feature = "std")]
     ReadError(std::io::Error),
 }
 
+impl core::fmt::Display for SlipError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SlipError::InvalidEscape { byte, offset } => write!(
+                f,
+                "invalid escape sequence 0x{:02X} 0x{:02X} at offset {}",
+                ESC, byte, offset
+            ),
+            SlipError::OversizedPacket { limit } => {
+                write!(f, "packet exceeds the {} byte limit", limit)
+            }
+            SlipError::Truncated { len } => {
+                write!(f, "data ended after {} bytes of an incomplete frame", len)
+            }
+            #[cfg(feature = "checksum")]
+            SlipError::ChecksumMismatch { received, computed } => write!(
+                f,
+                "checksum mismatch: received 0x{:X}, computed 0x{:X}",
+                received, computed
+            ),
+            SlipError::EndOfStream => write!(f, "end of stream"),
+            #[cfg(feature = "std")]
+            SlipError::WouldBlock { len } => {
+                write!(f, "no data available after {} bytes of a frame", len)
+            }
+            #[cfg(feature = "std")]
+            SlipError::ReadError(_) => write!(f, "error reading SLIP data"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SlipError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SlipError::ReadError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 impl From<SlipError> for std::io::Error {
     fn from(err: SlipError) -> std::io::Error {
+        use std::io::ErrorKind;
+
         match err {
-            SlipError::FramingError => {
-                std::io::Error::new(std::io::ErrorKind::Other, format!("{:?}", err))
-            }
-            SlipError::OversizedPacket => {
-                std::io::Error::new(std::io::ErrorKind::Other, format!("{:?}", err))
+            SlipError::InvalidEscape { .. } | SlipError::OversizedPacket { .. } => {
+                std::io::Error::new(ErrorKind::InvalidData, err)
             }
-            SlipError::EndOfStream => {
-                std::io::Error::new(std::io::ErrorKind::Other, format!("{:?}", err))
+            #[cfg(feature = "checksum")]
+            SlipError::ChecksumMismatch { .. } => std::io::Error::new(ErrorKind::InvalidData, err),
+            SlipError::Truncated { .. } | SlipError::EndOfStream => {
+                std::io::Error::new(ErrorKind::UnexpectedEof, err)
             }
+            SlipError::WouldBlock { .. } => std::io::Error::new(ErrorKind::WouldBlock, err),
             SlipError::ReadError(err) => err,
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl From<std::io::Error> for SlipError {
     fn from(err: std::io::Error) -> Self {
         SlipError::ReadError(err)
     }
 }
 
-pub type SlipResult = std::result::Result<usize, self::SlipError>;
+pub type SlipResult = core::result::Result<usize, self::SlipError>;
 
+/// Progress report from [`SlipDecoder::decode_slice`]
 #[derive(Debug)]
+pub struct Decoded {
+    /// Number of encoded bytes consumed from the input slice
+    pub consumed: usize,
+    /// Number of decoded bytes written to the output slice
+    pub produced: usize,
+    /// `Ok(Some(len))` if an `END` token completed a frame of `len` bytes,
+    /// `Ok(None)` if the input was exhausted first, or the decoding error
+    pub frame: core::result::Result<Option<usize>, SlipError>,
+}
+
+/// Policy for recovering from an `ESC` token followed by an invalid byte
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum EscapePolicy {
+    /// Report [`SlipError::InvalidEscape`] and discard the rest of the frame
+    #[default]
+    DropFrame,
+    /// Drop the `ESC` token and keep the byte that followed it
+    PassLiteral,
+    /// Keep both the `ESC` token and the byte that followed it
+    PassBoth,
+    /// Report [`SlipError::InvalidEscape`] and reject all further input
+    /// until the decoder is reset
+    Abort,
+}
+
+/// Policy for a frame left incomplete when the input ends
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum EofPolicy {
+    /// Silently discard the partial frame
+    #[default]
+    Discard,
+    /// Deliver the partial frame as the final item
+    Emit,
+    /// Report [`SlipError::Truncated`] with the length of the partial frame
+    Error,
+}
+
+#[derive(Debug, Clone, Copy)]
 enum State {
     Normal,
     Error,
     Escape,
+    Aborted { byte: u8, offset: u64 },
+}
+
+/// Effect of a single encoded byte on the state machine
+enum Token {
+    Skip,
+    Data(u8),
+    Data2([u8; 2]),
+    End,
+    Invalid(u8),
+    Aborted(u8, u64),
+}
+
+impl State {
+    fn next(self, value: u8) -> (State, Token) {
+        match self {
+            State::Normal => match value {
+                END => (State::Normal, Token::End),
+                ESC => (State::Escape, Token::Skip),
+                _ => (State::Normal, Token::Data(value)),
+            },
+            State::Error => match value {
+                END => (State::Normal, Token::End),
+                _ => (State::Error, Token::Skip),
+            },
+            State::Escape => match value {
+                ESC_END => (State::Normal, Token::Data(END)),
+                ESC_ESC => (State::Normal, Token::Data(ESC)),
+                _ => (State::Error, Token::Invalid(value)),
+            },
+            State::Aborted { byte, offset } => (self, Token::Aborted(byte, offset)),
+        }
+    }
+}
+
+/// Effect of a single encoded byte on the decoder
+enum Event {
+    Skip,
+    Data(u8),
+    Data2([u8; 2]),
+    Frame(usize),
+    Error(SlipError),
+    /// The input byte was not consumed
+    Reject(SlipError),
 }
 
 /// SLIP decoder context
 #[derive(Debug)]
 pub struct SlipDecoder {
     count: usize,
+    position: u64,
+    recoveries: u64,
+    escape_policy: EscapePolicy,
+    empty_frames: bool,
+    max_len: Option<usize>,
     state: State,
+    stats: SlipStats,
+}
+
+/// Builder for [`SlipDecoder`] configuration
+#[derive(Debug)]
+pub struct SlipDecoderBuilder {
+    escape_policy: EscapePolicy,
+    empty_frames: bool,
+    max_len: Option<usize>,
 }
 
 impl SlipDecoder {
     /// Creates a new context with the given maximum buffer size.
     pub fn new() -> Self {
-        Self {
-            count: 0usize,
-            state: State::Normal,
+        Self::builder().build()
+    }
+
+    /// Creates a new context that rejects frames longer than `max_len`
+    /// decoded bytes.
+    ///
+    /// An oversized frame is reported as [`SlipError::OversizedPacket`] as
+    /// soon as it exceeds the limit. The rest of the frame is discarded and
+    /// decoding resumes after the next `END` token, so any partial frame
+    /// already written to the sink should be discarded too.
+    pub fn with_max_len(max_len: usize) -> Self {
+        Self::builder().max_len(max_len).build()
+    }
+
+    /// Returns a builder without a length limit that drops frames with
+    /// invalid escape sequences
+    pub fn builder() -> SlipDecoderBuilder {
+        SlipDecoderBuilder {
+            escape_policy: EscapePolicy::DropFrame,
+            empty_frames: false,
+            max_len: None,
         }
     }
 
-    fn push(&mut self, sink: &mut dyn Write, value: u8) -> self::SlipResult {
-        match sink.write(&[value]) {
-            Ok(len) => {
-                if len != 1 {
-                    Err(SlipError::OversizedPacket)
+    /// Returns the maximum decoded frame length, if any
+    pub fn max_len(&self) -> Option<usize> {
+        self.max_len
+    }
+
+    #[cfg(any(feature = "async-codec", feature = "tokio-codec"))]
+    pub(crate) fn set_max_len(&mut self, max_len: Option<usize>) {
+        self.max_len = max_len;
+    }
+
+    /// Returns the policy applied to invalid escape sequences
+    pub fn escape_policy(&self) -> EscapePolicy {
+        self.escape_policy
+    }
+
+    /// Returns true if zero-length frames are delivered rather than skipped
+    pub fn empty_frames(&self) -> bool {
+        self.empty_frames
+    }
+
+    /// Returns the number of invalid escape sequences recovered from
+    /// without aborting
+    pub fn recoveries(&self) -> u64 {
+        self.recoveries
+    }
+
+    /// Returns the link quality counters accumulated since the decoder was
+    /// created or the counters were last reset
+    pub fn stats(&self) -> &SlipStats {
+        &self.stats
+    }
+
+    /// Clears the link quality counters
+    pub fn reset_stats(&mut self) {
+        self.stats = SlipStats::default();
+    }
+
+    /// Discards any partially decoded frame and returns to the initial state.
+    ///
+    /// This also clears the error of a decoder aborted by
+    /// [`EscapePolicy::Abort`].
+    pub fn reset(&mut self) {
+        self.count = 0usize;
+        self.state = State::Normal;
+    }
+
+    /// Discards the partial frame, skipping the rest of it up to the next
+    /// `END` token
+    #[cfg(feature = "std")]
+    pub(crate) fn drop_frame(&mut self) {
+        if self.in_frame() {
+            self.count = 0usize;
+            self.state = State::Error;
+        }
+    }
+
+    /// Returns true if decoding stopped in the middle of a frame
+    #[cfg(feature = "std")]
+    pub(crate) fn in_frame(&self) -> bool {
+        self.count > 0 || matches!(self.state, State::Escape)
+    }
+
+    /// Returns the number of decoded bytes in the current partial frame
+    #[cfg(feature = "std")]
+    pub(crate) fn partial_len(&self) -> usize {
+        self.count
+    }
+
+    /// Feeds one encoded byte through the state machine, given `room` for
+    /// decoded output.
+    fn feed(&mut self, value: u8, room: usize) -> Event {
+        let (mut state, mut token) = self.state.next(value);
+        let mut recovered = false;
+        let framing_error = matches!(token, Token::Invalid(_));
+
+        if let Token::Invalid(byte) = token {
+            let policy = self.escape_policy;
+
+            if policy == EscapePolicy::PassLiteral || policy == EscapePolicy::PassBoth {
+                // an END token always terminates the frame
+                state = State::Normal;
+                token = match (byte, policy) {
+                    (END, _) => Token::End,
+                    (_, EscapePolicy::PassLiteral) => Token::Data(byte),
+                    _ => Token::Data2([ESC, byte]),
+                };
+                recovered = true;
+            } else if policy == EscapePolicy::Abort {
+                state = State::Aborted {
+                    byte,
+                    offset: self.position,
+                };
+            } else {
+                recovered = true;
+            }
+        }
+
+        let event = match token {
+            Token::Skip => {
+                if let State::Error = self.state {
+                    self.stats.bytes_discarded += 1;
+                }
+
+                Event::Skip
+            }
+            Token::Data(_) | Token::Data2(_) if self.exceeds_max_len(&token) => {
+                let limit = self.max_len.unwrap_or(usize::MAX);
+
+                state = State::Error;
+                self.count = 0usize;
+                self.stats.oversize_drops += 1;
+                Event::Error(SlipError::OversizedPacket { limit })
+            }
+            Token::Data(value) => {
+                if room == 0 {
+                    return self.full();
+                }
+
+                self.count += 1;
+                Event::Data(value)
+            }
+            Token::Data2(values) => {
+                if room < values.len() {
+                    return self.full();
+                }
+
+                self.count += values.len();
+                Event::Data2(values)
+            }
+            Token::End => {
+                let len = self.count;
+
+                self.count = 0usize;
+
+                if len > 0 {
+                    self.stats.frames_decoded += 1;
+                    self.stats.bytes_out += len as u64;
+                    Event::Frame(len)
+                } else if matches!(self.state, State::Error) {
+                    // the END closing a dropped frame is not an empty frame
+                    Event::Skip
                 } else {
-                    self.count += 1;
-                    Ok(1usize)
+                    self.stats.empty_frames += 1;
+
+                    if self.empty_frames {
+                        Event::Frame(0)
+                    } else {
+                        Event::Skip
+                    }
                 }
             }
-            Err(error) => Err(error.into()),
+            Token::Invalid(byte) => {
+                self.count = 0usize;
+                Event::Error(SlipError::InvalidEscape {
+                    byte,
+                    offset: self.position,
+                })
+            }
+            Token::Aborted(byte, offset) => {
+                return Event::Reject(SlipError::InvalidEscape { byte, offset });
+            }
+        };
+
+        if recovered {
+            self.recoveries += 1;
+        }
+
+        if framing_error {
+            self.stats.framing_errors += 1;
+        }
+
+        self.state = state;
+        self.position += 1;
+        self.stats.bytes_in += 1;
+
+        event
+    }
+
+    fn exceeds_max_len(&self, token: &Token) -> bool {
+        let len = match token {
+            Token::Data2(values) => values.len(),
+            _ => 1,
+        };
+
+        self.max_len
+            .is_some_and(|max_len| self.count + len > max_len)
+    }
+
+    fn full(&self) -> Event {
+        Event::Reject(SlipError::OversizedPacket { limit: self.count })
+    }
+
+    /// Decodes SLIP data from `src` into `dst`, stopping after the first
+    /// `END` token that completes a frame.
+    ///
+    /// A frame may span several calls; decoded bytes are always written from
+    /// the start of `dst`, so the caller is responsible for collecting them.
+    /// If `dst` fills up before the frame is complete, decoding stops with
+    /// [`SlipError::OversizedPacket`] without consuming the byte that did not
+    /// fit. After a [`SlipError::InvalidEscape`] the remainder of the frame
+    /// is discarded.
+    pub fn decode_slice(&mut self, src: &[u8], dst: &mut [u8]) -> Decoded {
+        let mut consumed = 0usize;
+        let mut produced = 0usize;
+        let mut frame = Ok(None);
+
+        for value in src.iter() {
+            let event = self.feed(*value, dst.len() - produced);
+
+            if let Event::Reject(err) = event {
+                frame = Err(err);
+                break;
+            }
+
+            consumed += 1;
+
+            match event {
+                Event::Skip | Event::Reject(_) => {}
+                Event::Data(value) => {
+                    dst[produced] = value;
+                    produced += 1;
+                }
+                Event::Data2(values) => {
+                    dst[produced..produced + 2].copy_from_slice(&values);
+                    produced += 2;
+                }
+                Event::Frame(len) => {
+                    frame = Ok(Some(len));
+                    break;
+                }
+                Event::Error(err) => {
+                    frame = Err(err);
+                    break;
+                }
+            }
+        }
+
+        Decoded {
+            consumed,
+            produced,
+            frame,
+        }
+    }
+
+    /// Decodes the first complete frame in `buf` in place.
+    ///
+    /// Escape sequences only ever shrink a frame, so the decoded frame is
+    /// written over the start of `buf` and occupies `buf[..len]` when `frame`
+    /// is `Ok(Some(len))`. Only whole frames terminated by an `END` token are
+    /// consumed; a trailing incomplete frame is left untouched so that it can
+    /// be carried over and completed with more data. This method is not
+    /// intended to be interleaved with [`decode_slice`] in the middle of a
+    /// frame.
+    ///
+    /// See [`Frames`] for an iterator over all frames in a buffer.
+    ///
+    /// [`decode_slice`]: SlipDecoder::decode_slice
+    /// [`Frames`]: crate::Frames
+    pub fn decode_in_place(&mut self, buf: &mut [u8]) -> Decoded {
+        let mut consumed = 0usize;
+
+        while let Some(end) = buf[consumed..].iter().position(|value| *value == END) {
+            let mut produced = 0usize;
+
+            for index in consumed..=end + consumed {
+                match self.feed(buf[index], usize::MAX) {
+                    Event::Skip => {}
+                    Event::Data(value) => {
+                        buf[produced] = value;
+                        produced += 1;
+                    }
+                    Event::Data2(values) => {
+                        buf[produced..produced + 2].copy_from_slice(&values);
+                        produced += 2;
+                    }
+                    Event::Reject(err) => {
+                        return Decoded {
+                            consumed: index,
+                            produced,
+                            frame: Err(err),
+                        };
+                    }
+                    Event::Frame(len) => {
+                        return Decoded {
+                            consumed: index + 1,
+                            produced,
+                            frame: Ok(Some(len)),
+                        };
+                    }
+                    Event::Error(err) => {
+                        return Decoded {
+                            consumed: index + 1,
+                            produced,
+                            frame: Err(err),
+                        };
+                    }
+                }
+            }
+
+            consumed += end + 1;
+        }
+
+        Decoded {
+            consumed,
+            produced: 0usize,
+            frame: Ok(None),
         }
     }
 
@@ -77,58 +556,52 @@ impl SlipDecoder {
     /// # Arguments
     ///
     /// * `source` - Encoded SLIP data source implementing the std::io::Read
-    ///              trait
+    ///   trait
+    /// * `sink` - Output object implementing the std::io::Write trait
+    ///
+    /// Returns the length of the decoded frame written to the sink, or
+    /// [`SlipError::EndOfStream`] if the end of the source data was reached
+    /// before a complete frame.
     ///
-    /// Returns a Vec<u8> containing a decoded message or an empty Vec<u8> if
-    /// of the source data was reached.
+    /// Reads interrupted by a signal are retried. If a non-blocking source
+    /// reports [`ErrorKind::WouldBlock`], [`SlipError::WouldBlock`] is
+    /// returned instead; the bytes of the partial frame already written to
+    /// the sink stay valid and the next call continues the same frame, so
+    /// the returned length then covers the whole frame.
     ///
+    /// [`ErrorKind::WouldBlock`]: std::io::ErrorKind::WouldBlock
+    #[cfg(feature = "std")]
     pub fn decode(&mut self, source: &mut dyn Read, sink: &mut dyn Write) -> self::SlipResult {
-        for value in source.bytes() {
-            let value = value?;
+        let mut value = [0u8; 1];
 
-            match self.state {
-                State::Normal => match value {
-                    END => {
-                        if self.count > 0 {
-                            let len = self.count;
+        loop {
+            match source.read(&mut value) {
+                Ok(0) => return Err(SlipError::EndOfStream),
+                Ok(_) => {}
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+                    return Err(SlipError::WouldBlock { len: self.count });
+                }
+                Err(error) => return Err(error.into()),
+            }
 
-                            self.count = 0usize;
+            let mut output = [0u8; 2];
+            let decoded = self.decode_slice(&value, &mut output);
 
-                            return Ok(len);
-                        }
-                    }
-                    ESC => {
-                        self.state = State::Escape;
-                    }
-                    _ => {
-                        self.push(sink, value)?;
-                    }
-                },
-                State::Error => {
-                    if value == END {
-                        self.count = 0usize;
-                        self.state = State::Normal;
-                    }
+            if decoded.produced > 0 {
+                let len = sink.write(&output[..decoded.produced])?;
+
+                if len != decoded.produced {
+                    self.count -= decoded.produced - len;
+
+                    return Err(SlipError::OversizedPacket { limit: self.count });
                 }
-                State::Escape => match value {
-                    ESC_END => {
-                        self.push(sink, END)?;
-                        self.state = State::Normal;
-                    }
-                    ESC_ESC => {
-                        self.push(sink, ESC)?;
-                        self.state = State::Normal;
-                    }
-                    _ => {
-                        self.state = State::Error;
+            }
 
-                        return Err(SlipError::FramingError);
-                    }
-                },
+            if let Some(len) = decoded.frame? {
+                return Ok(len);
             }
         }
-
-        Err(SlipError::EndOfStream)
     }
 }
 
@@ -138,9 +611,149 @@ impl Default for SlipDecoder {
     }
 }
 
+impl SlipDecoderBuilder {
+    /// Sets the recovery from invalid escape sequences
+    pub fn escape_policy(self, escape_policy: EscapePolicy) -> Self {
+        Self {
+            escape_policy,
+            ..self
+        }
+    }
+
+    /// Deliver back-to-back `END` tokens as zero-length frames, e.g. for
+    /// keepalives, instead of skipping them as RFC 1055 suggests.
+    ///
+    /// The leading `END` that most encoders send before each frame then
+    /// decodes as an empty frame too, so peers sending empty frames should
+    /// disable it (see [`SlipEncoder::encode_empty`]).
+    ///
+    /// [`SlipEncoder::encode_empty`]: crate::SlipEncoder::encode_empty
+    pub fn empty_frames(self, empty_frames: bool) -> Self {
+        Self {
+            empty_frames,
+            ..self
+        }
+    }
+
+    /// Limit decoded frames to `max_len` bytes (see [`SlipDecoder::with_max_len`])
+    pub fn max_len(self, max_len: usize) -> Self {
+        Self {
+            max_len: Some(max_len),
+            ..self
+        }
+    }
+
+    /// Creates the configured decoder
+    pub fn build(self) -> SlipDecoder {
+        SlipDecoder {
+            count: 0usize,
+            position: 0u64,
+            recoveries: 0u64,
+            escape_policy: self.escape_policy,
+            empty_frames: self.empty_frames,
+            max_len: self.max_len,
+            state: State::Normal,
+            stats: SlipStats::default(),
+        }
+    }
+}
+
 #[cfg(test)]
+mod slice_tests {
+    use super::*;
+
+    #[test]
+    fn slice_decode() {
+        const INPUT: [u8; 9] = [0xc0, 0x01, 0xdb, 0xdc, 0x03, 0xc0, 0x04, 0x05, 0xc0];
+        const DATA_1: [u8; 3] = [0x01, 0xc0, 0x03];
+        const DATA_2: [u8; 2] = [0x04, 0x05];
+
+        let mut slip = SlipDecoder::new();
+        let mut buf = [0u8; 8];
+
+        let decoded = slip.decode_slice(&INPUT, &mut buf);
+        assert_eq!(6, decoded.consumed);
+        assert_eq!(DATA_1.len(), decoded.produced);
+        assert_eq!(Some(DATA_1.len()), decoded.frame.unwrap());
+        assert_eq!(&DATA_1, &buf[..decoded.produced]);
+
+        let decoded = slip.decode_slice(&INPUT[6..], &mut buf);
+        assert_eq!(3, decoded.consumed);
+        assert_eq!(Some(DATA_2.len()), decoded.frame.unwrap());
+        assert_eq!(&DATA_2, &buf[..decoded.produced]);
+    }
+
+    #[test]
+    fn slice_decode_output_full() {
+        const INPUT: [u8; 5] = [0xc0, 0x01, 0x02, 0x03, 0xc0];
+
+        let mut slip = SlipDecoder::new();
+        let mut buf = [0u8; 2];
+
+        let decoded = slip.decode_slice(&INPUT, &mut buf);
+        assert_eq!(3, decoded.consumed);
+        assert_eq!(2, decoded.produced);
+        assert!(matches!(
+            decoded.frame,
+            Err(SlipError::OversizedPacket { limit: 2 })
+        ));
+
+        // resume once more output space is available
+        let decoded = slip.decode_slice(&INPUT[3..], &mut buf);
+        assert_eq!(2, decoded.consumed);
+        assert_eq!(0x03, buf[0]);
+        assert_eq!(Some(3), decoded.frame.unwrap());
+    }
+
+    #[test]
+    fn slice_decode_framing_error() {
+        const INPUT: [u8; 8] = [0xc0, 0x01, 0xdb, 0x02, 0x03, 0xc0, 0x04, 0xc0];
+
+        let mut slip = SlipDecoder::new();
+        let mut buf = [0u8; 8];
+
+        let decoded = slip.decode_slice(&INPUT, &mut buf);
+        assert_eq!(4, decoded.consumed);
+        assert!(matches!(
+            decoded.frame,
+            Err(SlipError::InvalidEscape {
+                byte: 0x02,
+                offset: 3
+            })
+        ));
+
+        let decoded = slip.decode_slice(&INPUT[4..], &mut buf);
+        assert_eq!(4, decoded.consumed);
+        assert_eq!(Some(1), decoded.frame.unwrap());
+        assert_eq!(0x04, buf[0]);
+    }
+
+    #[test]
+    fn in_place_decode() {
+        let mut input = [0xc0, 0x01, 0xdb, 0xdc, 0x03, 0xc0, 0x04, 0x05];
+        const DATA: [u8; 3] = [0x01, 0xc0, 0x03];
+
+        let mut slip = SlipDecoder::new();
+
+        let decoded = slip.decode_in_place(&mut input);
+        assert_eq!(6, decoded.consumed);
+        assert_eq!(Some(DATA.len()), decoded.frame.unwrap());
+        assert_eq!(&DATA, &input[..DATA.len()]);
+
+        // incomplete frames are left untouched
+        let decoded = slip.decode_in_place(&mut input[6..]);
+        assert_eq!(0, decoded.consumed);
+        assert!(decoded.frame.unwrap().is_none());
+        assert_eq!(&[0x04, 0x05], &input[6..]);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
+    use crate::test_util::{Script, ScriptedReader};
+    use std::collections::VecDeque;
+    use std::io::ErrorKind;
 
     #[test]
     fn empty_decode() {
@@ -153,6 +766,76 @@ mod tests {
         assert!(buf.is_empty());
     }
 
+    #[test]
+    fn deliver_empty_frames() {
+        const INPUT: [u8; 8] = [0xc0, 0x01, 0xc0, 0xc0, 0xdb, 0x02, 0xc0, 0xc0];
+
+        let mut slip = SlipDecoder::builder().empty_frames(true).build();
+        let mut source = INPUT.as_ref();
+        let mut buf: Vec<u8> = Vec::new();
+        assert_eq!(0, slip.decode(&mut source, &mut buf).unwrap());
+        assert_eq!(1, slip.decode(&mut source, &mut buf).unwrap());
+        assert_eq!(0, slip.decode(&mut source, &mut buf).unwrap());
+        assert!(slip.decode(&mut source, &mut buf).is_err());
+        // the END closing the dropped frame is not an empty frame
+        assert_eq!(0, slip.decode(&mut source, &mut buf).unwrap());
+        assert!(source.is_empty());
+        assert_eq!(&[0x01], buf.as_slice());
+        assert_eq!(3, slip.stats().empty_frames);
+    }
+
+    #[test]
+    fn retry_interrupted_read() {
+        let mut source = ScriptedReader(VecDeque::from(vec![
+            Script::Data(&[0xc0, 0x01]),
+            Script::Fail(ErrorKind::Interrupted),
+            Script::Data(&[0x02, 0xc0]),
+        ]));
+
+        let mut slip = SlipDecoder::new();
+        let mut buf: Vec<u8> = Vec::new();
+        assert_eq!(2, slip.decode(&mut source, &mut buf).unwrap());
+        assert_eq!(&[0x01, 0x02], buf.as_slice());
+    }
+
+    #[test]
+    fn would_block_keeps_partial_frame() {
+        let mut source = ScriptedReader(VecDeque::from(vec![
+            Script::Data(&[0xc0, 0x01, 0xdb]),
+            Script::Fail(ErrorKind::WouldBlock),
+            Script::Fail(ErrorKind::WouldBlock),
+            Script::Data(&[0xdc, 0x03, 0xc0]),
+            Script::Fail(ErrorKind::WouldBlock),
+            Script::Fail(ErrorKind::BrokenPipe),
+        ]));
+
+        let mut slip = SlipDecoder::new();
+        let mut buf: Vec<u8> = Vec::new();
+        assert!(matches!(
+            slip.decode(&mut source, &mut buf),
+            Err(SlipError::WouldBlock { len: 1 })
+        ));
+        assert!(matches!(
+            slip.decode(&mut source, &mut buf),
+            Err(SlipError::WouldBlock { len: 1 })
+        ));
+        // the escape sequence split by the errors is completed
+        assert_eq!(3, slip.decode(&mut source, &mut buf).unwrap());
+        assert_eq!(&[0x01, 0xc0, 0x03], buf.as_slice());
+        assert!(matches!(
+            slip.decode(&mut source, &mut buf),
+            Err(SlipError::WouldBlock { len: 0 })
+        ));
+        assert!(matches!(
+            slip.decode(&mut source, &mut buf),
+            Err(SlipError::ReadError(err)) if err.kind() == ErrorKind::BrokenPipe
+        ));
+        assert!(matches!(
+            slip.decode(&mut source, &mut buf),
+            Err(SlipError::EndOfStream)
+        ));
+    }
+
     #[test]
     fn simple_decode() {
         const INPUT: [u8; 7] = [0xc0, 0x01, 0x02, 0x03, 0x04, 0x05, 0xc0];
@@ -244,4 +927,214 @@ mod tests {
             assert_eq!(&DATA_2, buf.as_slice());
         }
     }
+
+    #[test]
+    fn error_offsets() {
+        const INPUT: [u8; 10] = [0xc0, 0x01, 0xdb, 0x02, 0xc0, 0x03, 0xc0, 0xdb, 0xdb, 0xc0];
+
+        let mut slip = SlipDecoder::new();
+        let mut buf: Vec<u8> = Vec::new();
+        let reader: &mut dyn std::io::Read = &mut INPUT.as_ref();
+
+        let err = slip.decode(reader, &mut buf).unwrap_err();
+        assert!(matches!(
+            err,
+            SlipError::InvalidEscape {
+                byte: 0x02,
+                offset: 3
+            }
+        ));
+        assert_eq!(1, slip.decode(reader, &mut buf).unwrap());
+        assert_eq!(
+            "invalid escape sequence 0xDB 0xDB at offset 8",
+            slip.decode(reader, &mut buf).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn io_error_kinds() {
+        use std::io::ErrorKind;
+
+        let err: std::io::Error = SlipError::InvalidEscape { byte: 0, offset: 0 }.into();
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+        let err: std::io::Error = SlipError::OversizedPacket { limit: 1 }.into();
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+        let err: std::io::Error = SlipError::Truncated { len: 1 }.into();
+        assert_eq!(ErrorKind::UnexpectedEof, err.kind());
+        let err: std::io::Error = SlipError::ReadError(ErrorKind::BrokenPipe.into()).into();
+        assert_eq!(ErrorKind::BrokenPipe, err.kind());
+
+        let err = SlipError::ReadError(ErrorKind::BrokenPipe.into());
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    fn decode_all(slip: &mut SlipDecoder, input: &[u8]) -> Vec<Result<Vec<u8>, SlipError>> {
+        let mut reader = input;
+        let mut frames = Vec::new();
+
+        loop {
+            let mut buf: Vec<u8> = Vec::new();
+
+            match slip.decode(&mut reader, &mut buf) {
+                Ok(_) => frames.push(Ok(buf)),
+                Err(SlipError::EndOfStream) => return frames,
+                Err(err) => frames.push(Err(err)),
+            }
+        }
+    }
+
+    const INVALID_ESCAPES: [u8; 13] = [
+        0xc0, 0x01, 0xdb, 0x02, 0x03, 0xc0, 0x04, 0xdb, 0xc0, 0x05, 0xc0, 0x06, 0xc0,
+    ];
+
+    #[test]
+    fn escape_policy_drop_frame() {
+        let mut slip = SlipDecoder::new();
+        let frames = decode_all(&mut slip, &INVALID_ESCAPES);
+        assert_eq!(3, frames.len());
+        assert!(matches!(
+            frames[0],
+            Err(SlipError::InvalidEscape { byte: 0x02, .. })
+        ));
+        // ESC END leaves the decoder discarding through the next END
+        assert!(matches!(
+            frames[1],
+            Err(SlipError::InvalidEscape { byte: 0xc0, .. })
+        ));
+        assert_eq!(vec![0x06], *frames[2].as_ref().unwrap());
+        assert_eq!(2, slip.recoveries());
+    }
+
+    #[test]
+    fn escape_policy_pass_literal() {
+        let mut slip = SlipDecoder::builder()
+            .escape_policy(EscapePolicy::PassLiteral)
+            .build();
+        let frames: Vec<Vec<u8>> = decode_all(&mut slip, &INVALID_ESCAPES)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            vec![vec![0x01, 0x02, 0x03], vec![0x04], vec![0x05], vec![0x06]],
+            frames
+        );
+        assert_eq!(2, slip.recoveries());
+    }
+
+    #[test]
+    fn escape_policy_pass_both() {
+        let mut slip = SlipDecoder::builder()
+            .escape_policy(EscapePolicy::PassBoth)
+            .build();
+        let frames: Vec<Vec<u8>> = decode_all(&mut slip, &INVALID_ESCAPES)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            vec![
+                vec![0x01, 0xdb, 0x02, 0x03],
+                vec![0x04],
+                vec![0x05],
+                vec![0x06]
+            ],
+            frames
+        );
+        assert_eq!(2, slip.recoveries());
+    }
+
+    #[test]
+    fn escape_policy_abort() {
+        let mut slip = SlipDecoder::builder()
+            .escape_policy(EscapePolicy::Abort)
+            .build();
+        let mut buf = [0u8; 16];
+
+        let decoded = slip.decode_slice(&INVALID_ESCAPES, &mut buf);
+        assert_eq!(4, decoded.consumed);
+        assert!(matches!(
+            decoded.frame,
+            Err(SlipError::InvalidEscape {
+                byte: 0x02,
+                offset: 3
+            })
+        ));
+
+        // all further input is rejected until reset
+        let decoded = slip.decode_slice(&INVALID_ESCAPES[4..], &mut buf);
+        assert_eq!(0, decoded.consumed);
+        assert!(matches!(
+            decoded.frame,
+            Err(SlipError::InvalidEscape {
+                byte: 0x02,
+                offset: 3
+            })
+        ));
+        assert_eq!(0, slip.recoveries());
+
+        slip.reset();
+        let decoded = slip.decode_slice(&INVALID_ESCAPES[9..], &mut buf);
+        assert_eq!(Some(1), decoded.frame.unwrap());
+    }
+
+    #[test]
+    fn max_len_resync() {
+        const INPUT: [u8; 15] = [
+            0xc0, 0x01, 0x02, 0x03, 0x04, 0x05, 0xdb, 0xdc, 0xc0, 0x06, 0x07, 0x08, 0xc0, 0x09,
+            0xc0,
+        ];
+
+        let mut slip = SlipDecoder::with_max_len(3);
+        let frames = decode_all(&mut slip, &INPUT);
+        assert_eq!(3, frames.len());
+        assert!(matches!(
+            frames[0],
+            Err(SlipError::OversizedPacket { limit: 3 })
+        ));
+        assert_eq!(vec![0x06, 0x07, 0x08], *frames[1].as_ref().unwrap());
+        assert_eq!(vec![0x09], *frames[2].as_ref().unwrap());
+    }
+
+    #[test]
+    fn max_len_bounds_sink() {
+        // a line that never sends END must not grow the sink without bound
+        let input = vec![0x55u8; 1 << 16];
+
+        let mut slip = SlipDecoder::with_max_len(crate::MAX_PACKET_SIZE);
+        let mut buf: Vec<u8> = Vec::new();
+        let err = slip.decode(&mut input.as_slice(), &mut buf).unwrap_err();
+        assert!(matches!(
+            err,
+            SlipError::OversizedPacket {
+                limit: crate::MAX_PACKET_SIZE
+            }
+        ));
+        assert_eq!(crate::MAX_PACKET_SIZE, buf.len());
+    }
+
+    #[test]
+    fn stats() {
+        const INPUT: [u8; 20] = [
+            0xc0, 0x01, 0x02, 0xc0, 0xc0, 0x01, 0x02, 0x03, 0x04, 0x08, 0xc0, 0x05, 0xdb, 0x06,
+            0x07, 0xc0, 0xdb, 0xdd, 0xc0, 0xc0,
+        ];
+
+        let mut slip = SlipDecoder::with_max_len(3);
+        let frames = decode_all(&mut slip, &INPUT);
+        assert_eq!(4, frames.len());
+        assert_eq!(
+            SlipStats {
+                frames_decoded: 2,
+                bytes_in: 20,
+                bytes_out: 3,
+                empty_frames: 3,
+                framing_errors: 1,
+                oversize_drops: 1,
+                bytes_discarded: 2,
+            },
+            *slip.stats()
+        );
+
+        slip.reset_stats();
+        assert_eq!(SlipStats::default(), *slip.stats());
+    }
 }
@@ -1,15 +1,220 @@
 use crate::{END, ESC, ESC_END, ESC_ESC};
+use memchr::memchr2;
+
+/// Progress report from [`SlipEncoder::encode_slice`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Encoded {
+    /// Number of bytes consumed from the input slice
+    pub consumed: usize,
+    /// Number of encoded bytes written to the output slice
+    pub produced: usize,
+}
+
+/// Error returned by [`SlipEncoder::encode_to_slice`] when the output slice
+/// cannot hold the encoded frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTooSmall {
+    /// Length of the encoded frame
+    pub required: usize,
+}
+
+impl core::fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "buffer too small: {} bytes required", self.required)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BufferTooSmall {}
+
+/// Policy for flushing the sink after encoding frames
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FlushPolicy {
+    /// Never flush; the caller is responsible for flushing the sink
+    Never,
+    /// Flush after every frame
+    #[default]
+    PerFrame,
+    /// Flush after every `n` frames
+    EveryNFrames(core::num::NonZeroUsize),
+}
+
+impl FlushPolicy {
+    /// Returns true if the sink should be flushed after `frames` frames
+    /// have been written since the last flush
+    pub fn should_flush(&self, frames: usize) -> bool {
+        match self {
+            FlushPolicy::Never => false,
+            FlushPolicy::PerFrame => frames > 0,
+            FlushPolicy::EveryNFrames(n) => frames >= n.get(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// No frame is in progress
+    Idle,
+    /// A frame is in progress
+    Frame,
+    /// The second byte of an escape sequence is waiting for output space
+    Escape(u8),
+}
 
 /// SLIP encoder context
 #[derive(Debug)]
 pub struct SlipEncoder {
     begin_with_end: bool,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    flush_policy: FlushPolicy,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    frames: usize,
+    state: State,
+}
+
+/// Builder for [`SlipEncoder`] configuration
+#[derive(Debug)]
+pub struct SlipEncoderBuilder {
+    begin_with_end: bool,
+    flush_policy: FlushPolicy,
 }
 
 impl SlipEncoder {
-    /// Creates a new encoder context
+    /// Creates a new encoder context that flushes the sink after every frame
     pub fn new(begin_with_end: bool) -> Self {
-        Self { begin_with_end }
+        Self::builder().begin_with_end(begin_with_end).build()
+    }
+
+    /// Returns a builder that begins frames with an `END` token and flushes
+    /// after every frame
+    pub fn builder() -> SlipEncoderBuilder {
+        SlipEncoderBuilder {
+            begin_with_end: true,
+            flush_policy: FlushPolicy::PerFrame,
+        }
+    }
+
+    /// Returns the exact length of the SLIP frame that encodes `buf`,
+    /// including the optional leading `END` token.
+    pub fn encoded_len(buf: &[u8], begin_with_end: bool) -> usize {
+        let escapes = memchr::memchr2_iter(END, ESC, buf).count();
+
+        usize::from(begin_with_end) + buf.len() + escapes + 1
+    }
+
+    /// Encodes `buf` as a complete SLIP frame at the start of `dst`.
+    ///
+    /// Returns the length of the encoded frame, or [`BufferTooSmall`] without
+    /// modifying `dst` if it cannot hold the whole frame. See
+    /// [`encoded_len`] to size the output in advance.
+    ///
+    /// [`encoded_len`]: SlipEncoder::encoded_len
+    pub fn encode_to_slice(&self, mut buf: &[u8], dst: &mut [u8]) -> Result<usize, BufferTooSmall> {
+        let required = Self::encoded_len(buf, self.begin_with_end);
+        let mut dst = match dst.get_mut(..required) {
+            Some(dst) => dst,
+            None => return Err(BufferTooSmall { required }),
+        };
+
+        if self.begin_with_end {
+            dst[0] = END;
+            dst = &mut dst[1..];
+        }
+
+        while let Some(index) = memchr2(END, ESC, buf) {
+            let escape = if buf[index] == END { ESC_END } else { ESC_ESC };
+
+            dst[..index].copy_from_slice(&buf[..index]);
+            dst[index] = ESC;
+            dst[index + 1] = escape;
+            dst = &mut dst[index + 2..];
+            buf = &buf[index + 1..];
+        }
+
+        dst[..buf.len()].copy_from_slice(buf);
+        dst[buf.len()] = END;
+
+        Ok(required)
+    }
+
+    /// Encodes as much of `src` into `dst` as will fit, starting a new frame
+    /// if one is not already in progress and `src` is not empty.
+    ///
+    /// The frame remains open across calls so that a packet may be encoded
+    /// in pieces. Call [`finish_slice`] to terminate it.
+    ///
+    /// Returns the number of bytes consumed from `src` and written to `dst`.
+    ///
+    /// [`finish_slice`]: SlipEncoder::finish_slice
+    pub fn encode_slice(&mut self, src: &[u8], dst: &mut [u8]) -> Encoded {
+        let mut consumed = 0usize;
+        let mut produced = 0usize;
+
+        while produced < dst.len() {
+            match self.state {
+                State::Idle => {
+                    if src.is_empty() {
+                        break;
+                    }
+
+                    if self.begin_with_end {
+                        dst[produced] = END;
+                        produced += 1;
+                    }
+                    self.state = State::Frame;
+                }
+                State::Escape(value) => {
+                    dst[produced] = value;
+                    produced += 1;
+                    self.state = State::Frame;
+                }
+                State::Frame => {
+                    let run = &src[consumed..];
+                    let run = &run[..run.len().min(dst.len() - produced)];
+
+                    if run.is_empty() {
+                        break;
+                    }
+
+                    let len = memchr2(END, ESC, run).unwrap_or(run.len());
+
+                    dst[produced..produced + len].copy_from_slice(&run[..len]);
+                    consumed += len;
+                    produced += len;
+
+                    if let Some(value) = run.get(len) {
+                        dst[produced] = ESC;
+                        consumed += 1;
+                        produced += 1;
+                        self.state = State::Escape(if *value == END { ESC_END } else { ESC_ESC });
+                    }
+                }
+            }
+        }
+
+        Encoded { consumed, produced }
+    }
+
+    /// Terminates the current frame by writing any pending bytes followed by
+    /// an `END` token to `dst`.
+    ///
+    /// Returns the number of bytes written, or `None` without writing
+    /// anything if `dst` is too small. At most three bytes are required.
+    pub fn finish_slice(&mut self, dst: &mut [u8]) -> Option<usize> {
+        let pending: &[u8] = match self.state {
+            State::Idle if self.begin_with_end => &[END, END],
+            State::Idle | State::Frame => &[END],
+            State::Escape(ESC_END) => &[ESC_END, END],
+            State::Escape(_) => &[ESC_ESC, END],
+        };
+        let dst = dst.get_mut(..pending.len())?;
+
+        dst.copy_from_slice(pending);
+        self.state = State::Idle;
+
+        Some(pending.len())
     }
 
     /// Encodes the given buffer in a SLIP frame and forwards it to the sink.
@@ -19,35 +224,161 @@ impl SlipEncoder {
     /// * `buf` - input data buffer for encoding
     /// * `sink` - output object implementing the std::io::Write trait
     ///
-    /// Returns the number of bytes written to the sink.
+    /// Returns the number of bytes written to the sink. The sink is flushed
+    /// according to the encoder's [`FlushPolicy`].
     ///
+    #[cfg(feature = "std")]
     pub fn encode(&mut self, buf: &[u8], sink: &mut dyn std::io::Write) -> std::io::Result<usize> {
-        let mut len = if self.begin_with_end {
-            sink.write(&[END])?
-        } else {
-            0
-        };
+        self.encode_parts(&[buf], sink)
+    }
 
-        for value in buf.iter() {
-            match *value {
-                END => {
-                    len += sink.write(&[ESC, ESC_END])?;
-                }
-                ESC => {
-                    len += sink.write(&[ESC, ESC_ESC])?;
-                }
-                _ => {
-                    len += sink.write(&[*value])?;
-                }
-            }
+    /// Sends an explicit empty frame, a single `END` token, regardless of
+    /// the leading `END` setting.
+    ///
+    /// Receivers that deliver empty frames (see
+    /// [`SlipDecoderBuilder::empty_frames`]) yield it as a zero-length item,
+    /// e.g. for keepalives. Returns the number of bytes written to the sink.
+    ///
+    /// [`SlipDecoderBuilder::empty_frames`]: crate::SlipDecoderBuilder::empty_frames
+    #[cfg(feature = "std")]
+    pub fn encode_empty(&mut self, sink: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        sink.write_all(&[END])?;
+        self.frames_written(1, sink)?;
+
+        Ok(1)
+    }
+
+    /// Encodes the concatenation of `parts` as a single SLIP frame
+    #[cfg(feature = "std")]
+    pub(crate) fn encode_parts(
+        &mut self,
+        parts: &[&[u8]],
+        sink: &mut dyn std::io::Write,
+    ) -> std::io::Result<usize> {
+        let len = self.write_frame(parts, sink)?;
+
+        self.frames_written(1, sink)?;
+
+        Ok(len)
+    }
+
+    /// Encodes each buffer in its own SLIP frame and forwards them all to
+    /// the sink, flushing at most once afterwards.
+    ///
+    /// Returns the total number of bytes written to the sink.
+    #[cfg(feature = "std")]
+    pub fn encode_batch(
+        &mut self,
+        bufs: &[&[u8]],
+        sink: &mut dyn std::io::Write,
+    ) -> std::io::Result<usize> {
+        let mut len = 0usize;
+
+        for buf in bufs.iter() {
+            len += self.write_frame(&[buf], sink)?;
         }
 
-        len += sink.write(&[END])?;
+        self.frames_written(bufs.len(), sink)?;
 
-        sink.flush()?;
+        Ok(len)
+    }
+
+    /// Encodes an IPv4 or IPv6 packet in a SLIP frame and forwards it to the
+    /// sink.
+    ///
+    /// The packet is checked with [`ip::validate`] first; anything else is
+    /// refused with an [`ErrorKind::InvalidInput`] error wrapping the
+    /// [`IpError`] and nothing is written.
+    ///
+    /// [`ip::validate`]: crate::ip::validate
+    /// [`ErrorKind::InvalidInput`]: std::io::ErrorKind::InvalidInput
+    /// [`IpError`]: crate::ip::IpError
+    #[cfg(all(feature = "ip", feature = "std"))]
+    pub fn encode_ip(
+        &mut self,
+        packet: &[u8],
+        sink: &mut dyn std::io::Write,
+    ) -> std::io::Result<usize> {
+        crate::ip::validate(packet)?;
+
+        self.encode(packet, sink)
+    }
+
+    #[cfg(feature = "std")]
+    fn write_frame(
+        &self,
+        parts: &[&[u8]],
+        sink: &mut dyn std::io::Write,
+    ) -> std::io::Result<usize> {
+        let mut len = 0usize;
+
+        if self.begin_with_end {
+            sink.write_all(&[END])?;
+            len += 1;
+        }
+
+        for part in parts.iter() {
+            let mut buf = *part;
+
+            // write each run of bytes that need no escaping with a single call
+            while let Some(index) = memchr2(END, ESC, buf) {
+                let escape = if buf[index] == END {
+                    [ESC, ESC_END]
+                } else {
+                    [ESC, ESC_ESC]
+                };
+
+                sink.write_all(&buf[..index])?;
+                sink.write_all(&escape)?;
+                len += index + escape.len();
+                buf = &buf[index + 1..];
+            }
+
+            sink.write_all(buf)?;
+            len += buf.len();
+        }
+
+        sink.write_all(&[END])?;
+        len += 1;
 
         Ok(len)
     }
+
+    /// Counts `frames` written to the sink and flushes it according to the
+    /// flush policy
+    #[cfg(feature = "std")]
+    pub(crate) fn frames_written(
+        &mut self,
+        frames: usize,
+        sink: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        self.frames += frames;
+
+        if self.flush_policy.should_flush(self.frames) {
+            self.flush(sink)?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the sink and restarts the count of unflushed frames
+    #[cfg(feature = "std")]
+    pub(crate) fn flush(&mut self, sink: &mut dyn std::io::Write) -> std::io::Result<()> {
+        self.frames = 0;
+        sink.flush()
+    }
+
+    /// Returns the exact length of the SLIP frame that encodes the
+    /// concatenation of `parts`
+    #[cfg(any(feature = "async-codec", feature = "tokio-codec"))]
+    pub(crate) fn frame_len(&self, parts: &[&[u8]]) -> usize {
+        let escaped: usize = parts
+            .iter()
+            .map(|part| Self::encoded_len(part, false) - 1)
+            .sum();
+
+        usize::from(self.begin_with_end) + escaped + 1
+    }
 }
 
 impl Default for SlipEncoder {
@@ -56,9 +387,126 @@ impl Default for SlipEncoder {
     }
 }
 
+impl SlipEncoderBuilder {
+    /// Begin every frame with an `END` token to flush line noise
+    pub fn begin_with_end(self, begin_with_end: bool) -> Self {
+        Self {
+            begin_with_end,
+            ..self
+        }
+    }
+
+    /// Sets when the sink is flushed after encoding frames
+    pub fn flush_policy(self, flush_policy: FlushPolicy) -> Self {
+        Self {
+            flush_policy,
+            ..self
+        }
+    }
+
+    /// Creates the configured encoder
+    pub fn build(self) -> SlipEncoder {
+        SlipEncoder {
+            begin_with_end: self.begin_with_end,
+            flush_policy: self.flush_policy,
+            frames: 0,
+            state: State::Idle,
+        }
+    }
+}
+
 #[cfg(test)]
+mod slice_tests {
+    use super::*;
+
+    #[test]
+    fn slice_encode() {
+        const INPUT: [u8; 3] = [0x01, END, ESC];
+        const EXPECTED: [u8; 7] = [0xc0, 0x01, ESC, ESC_END, ESC, ESC_ESC, 0xc0];
+        let mut output = [0u8; 7];
+
+        let mut slip = SlipEncoder::new(true);
+        let encoded = slip.encode_slice(&INPUT, &mut output);
+        assert_eq!(INPUT.len(), encoded.consumed);
+        assert_eq!(6, encoded.produced);
+        assert_eq!(Some(1), slip.finish_slice(&mut output[6..]));
+        assert_eq!(&EXPECTED, &output);
+    }
+
+    #[test]
+    fn slice_encode_in_pieces() {
+        const INPUT: [u8; 3] = [0x01, END, 0x03];
+        const EXPECTED: [u8; 6] = [0xc0, 0x01, ESC, ESC_END, 0x03, 0xc0];
+        let mut output = [0u8; 6];
+        let mut input = &INPUT[..];
+        let mut len = 0usize;
+
+        // a single byte of output space splits the escape sequence
+        let mut slip = SlipEncoder::new(true);
+        while len < 5 {
+            let encoded = slip.encode_slice(input, &mut output[len..len + 1]);
+            input = &input[encoded.consumed..];
+            len += encoded.produced;
+        }
+        assert!(input.is_empty());
+        assert_eq!(None, slip.finish_slice(&mut output[len..len]));
+        assert_eq!(Some(1), slip.finish_slice(&mut output[len..]));
+        assert_eq!(&EXPECTED, &output);
+    }
+
+    #[test]
+    fn slice_encode_empty() {
+        let mut output = [0u8; 2];
+
+        let mut slip = SlipEncoder::new(true);
+        assert_eq!(None, slip.finish_slice(&mut output[..1]));
+        assert_eq!(Some(2), slip.finish_slice(&mut output));
+        assert_eq!(&[END, END], &output);
+
+        let mut slip = SlipEncoder::new(false);
+        assert_eq!(Some(1), slip.finish_slice(&mut output));
+        assert_eq!(END, output[0]);
+    }
+
+    #[test]
+    fn encode_to_slice() {
+        const INPUT: [u8; 4] = [0x01, END, ESC, 0x03];
+        const EXPECTED: [u8; 8] = [0xc0, 0x01, ESC, ESC_END, ESC, ESC_ESC, 0x03, 0xc0];
+        let mut output = [0u8; 10];
+
+        assert_eq!(EXPECTED.len(), SlipEncoder::encoded_len(&INPUT, true));
+        assert_eq!(EXPECTED.len() - 1, SlipEncoder::encoded_len(&INPUT, false));
+
+        let slip = SlipEncoder::new(true);
+        let len = slip.encode_to_slice(&INPUT, &mut output).unwrap();
+        assert_eq!(EXPECTED.len(), len);
+        assert_eq!(&EXPECTED, &output[..len]);
+
+        let slip = SlipEncoder::new(false);
+        let len = slip.encode_to_slice(&INPUT, &mut output).unwrap();
+        assert_eq!(&EXPECTED[1..], &output[..len]);
+    }
+
+    #[test]
+    fn encode_to_slice_too_small() {
+        const INPUT: [u8; 3] = [0x01, END, 0x03];
+        let mut output = [0u8; 5];
+
+        let slip = SlipEncoder::new(true);
+        assert_eq!(
+            Err(BufferTooSmall { required: 6 }),
+            slip.encode_to_slice(&INPUT, &mut output)
+        );
+        assert_eq!(&[0u8; 5], &output);
+        assert_eq!(Ok(2), slip.encode_to_slice(&[], &mut output));
+        assert_eq!(&[END, END], &output[..2]);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
+    use crate::test_util::FlushCounter;
 
     #[test]
     fn empty_encode() {
@@ -71,6 +519,17 @@ mod tests {
         assert_eq!(&EXPECTED, output.as_slice());
     }
 
+    #[test]
+    fn encode_explicit_empty_frame() {
+        let mut output = Vec::<u8>::new();
+
+        let mut slip = SlipEncoder::new(true);
+        assert_eq!(1, slip.encode_empty(&mut output).unwrap());
+        slip.encode(&[0x01], &mut output).unwrap();
+        assert_eq!(1, slip.encode_empty(&mut output).unwrap());
+        assert_eq!(&[0xc0, 0xc0, 0x01, 0xc0, 0xc0], output.as_slice());
+    }
+
     #[test]
     fn encode_esc_esc_sequence() {
         const INPUT: [u8; 3] = [0x01, ESC, 0x03];
@@ -94,4 +553,78 @@ mod tests {
         assert_eq!(EXPECTED.len(), len);
         assert_eq!(&EXPECTED, output.as_slice());
     }
+
+    #[test]
+    fn encode_escape_runs() {
+        const INPUT: [u8; 8] = [END, 0x01, 0x02, ESC, ESC, 0x03, 0x04, END];
+        const EXPECTED: [u8; 14] = [
+            0xc0, ESC, ESC_END, 0x01, 0x02, ESC, ESC_ESC, ESC, ESC_ESC, 0x03, 0x04, ESC, ESC_END,
+            0xc0,
+        ];
+        let mut output = Vec::<u8>::new();
+
+        let mut slip = SlipEncoder::new(true);
+        let len = slip.encode(&INPUT, &mut output).unwrap();
+        assert_eq!(EXPECTED.len(), len);
+        assert_eq!(&EXPECTED, output.as_slice());
+    }
+
+    #[test]
+    fn encode_flush_policy() {
+        let mut output = FlushCounter::default();
+        let mut slip = SlipEncoder::new(true);
+        slip.encode(&[0x01], &mut output).unwrap();
+        slip.encode(&[0x02], &mut output).unwrap();
+        assert_eq!(2, output.flushes);
+
+        let mut output = FlushCounter::default();
+        let mut slip = SlipEncoder::builder()
+            .flush_policy(FlushPolicy::Never)
+            .build();
+        slip.encode(&[0x01], &mut output).unwrap();
+        slip.encode(&[0x02], &mut output).unwrap();
+        assert_eq!(0, output.flushes);
+
+        let mut output = FlushCounter::default();
+        let mut slip = SlipEncoder::builder()
+            .flush_policy(FlushPolicy::EveryNFrames(
+                core::num::NonZeroUsize::new(2).unwrap(),
+            ))
+            .build();
+        for _ in 0..5 {
+            slip.encode(&[0x01], &mut output).unwrap();
+        }
+        assert_eq!(2, output.flushes);
+    }
+
+    #[test]
+    fn encode_batch() {
+        const EXPECTED: [u8; 9] = [0xc0, 0x01, 0xc0, 0xc0, ESC, ESC_END, 0xc0, 0xc0, 0xc0];
+        let mut output = FlushCounter::default();
+
+        let mut slip = SlipEncoder::new(true);
+        let len = slip
+            .encode_batch(&[&[0x01], &[END], &[]], &mut output)
+            .unwrap();
+        assert_eq!(EXPECTED.len(), len);
+        assert_eq!(&EXPECTED, output.data.as_slice());
+        assert_eq!(1, output.flushes);
+    }
+
+    #[cfg(feature = "ip")]
+    #[test]
+    fn encode_ip() {
+        const PACKET: [u8; 20] = [
+            0x45, 0x00, 0x00, 0x14, 0x00, 0x00, 0x40, 0x00, 0x40, 0x00, 0xb8, 0xd1, 0xc0, 0xa8,
+            0x00, 0x01, 0xc0, 0xa8, 0x00, 0xc7,
+        ];
+
+        let mut slip = SlipEncoder::default();
+        let mut output = Vec::new();
+        assert_eq!(24, slip.encode_ip(&PACKET, &mut output).unwrap());
+
+        let err = slip.encode_ip(&PACKET[..19], &mut output).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+        assert_eq!(24, output.len());
+    }
 }
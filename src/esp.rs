@@ -0,0 +1,708 @@
+//! Espressif ROM bootloader serial protocol
+//!
+//! The protocol spoken by the ROM bootloader of ESP8266 and ESP32 chips (and
+//! by `esptool`) carries one command or response packet per SLIP frame.
+//! Every packet starts with an eight byte header:
+//!
+//! | Byte | Request                 | Response                   |
+//! |------|-------------------------|----------------------------|
+//! | 0    | direction, `0x00`       | direction, `0x01`          |
+//! | 1    | command opcode          | opcode of the request      |
+//! | 2-3  | length of the data      | length of the data         |
+//! | 4-7  | checksum of the payload | value, e.g. a register     |
+//!
+//! All fields are little endian. A response ends with status bytes: two for
+//! the ESP8266 ROM and the flasher stub, four for the ROM of the ESP32
+//! family. [`Request`] and [`Response`] are sent and received through
+//! [`SlipEncoder`] and [`SlipDecoder`].
+
+use crate::wire::read_u32_le;
+use crate::{SlipDecoder, SlipEncoder, SlipError};
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+/// Number of status bytes ending a response from the ESP32 family ROM
+pub const ROM_STATUS_LEN: usize = 4;
+
+/// Number of status bytes ending a response from the ESP8266 ROM or the
+/// flasher stub
+pub const STUB_STATUS_LEN: usize = 2;
+
+/// Seed of the payload checksum of `FLASH_DATA` and `MEM_DATA`
+pub const CHECKSUM_SEED: u8 = 0xEF;
+
+const REQUEST: u8 = 0x00;
+const RESPONSE: u8 = 0x01;
+const HEADER_LEN: usize = 8;
+
+/// Data of the `SYNC` command
+const SYNC_DATA: [u8; 36] = [
+    0x07, 0x07, 0x12, 0x20, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+    0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+    0x55, 0x55, 0x55, 0x55,
+];
+
+/// Command opcodes
+pub mod opcode {
+    pub const FLASH_BEGIN: u8 = 0x02;
+    pub const FLASH_DATA: u8 = 0x03;
+    pub const FLASH_END: u8 = 0x04;
+    pub const MEM_BEGIN: u8 = 0x05;
+    pub const MEM_END: u8 = 0x06;
+    pub const MEM_DATA: u8 = 0x07;
+    pub const SYNC: u8 = 0x08;
+    pub const WRITE_REG: u8 = 0x09;
+    pub const READ_REG: u8 = 0x0A;
+    pub const SPI_ATTACH: u8 = 0x0D;
+    pub const CHANGE_BAUDRATE: u8 = 0x0F;
+}
+
+/// Bootloader protocol error type
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EspError {
+    /// The SLIP frame could not be decoded
+    Slip(SlipError),
+    /// The `len` byte packet is too short or its length field is wrong
+    Malformed { len: usize },
+    /// The `len` bytes of data do not fit the 16-bit length field
+    TooLong { len: usize },
+    /// The packet does not travel in the expected direction
+    Direction(u8),
+    /// The request opcode is not supported
+    UnknownCommand(u8),
+    /// The response belongs to a different command
+    UnexpectedResponse { expected: u8, received: u8 },
+    /// The bootloader reported a failure with the given error code
+    Failed { opcode: u8, error: u8 },
+}
+
+impl core::fmt::Display for EspError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EspError::Slip(err) => write!(f, "{}", err),
+            EspError::Malformed { len } => write!(f, "malformed {} byte packet", len),
+            EspError::TooLong { len } => {
+                write!(f, "{} bytes of data exceed the 16-bit length field", len)
+            }
+            EspError::Direction(direction) => {
+                write!(f, "unexpected packet direction 0x{:02X}", direction)
+            }
+            EspError::UnknownCommand(opcode) => write!(f, "unknown command 0x{:02X}", opcode),
+            EspError::UnexpectedResponse { expected, received } => write!(
+                f,
+                "response to command 0x{:02X} while waiting for 0x{:02X}",
+                received, expected
+            ),
+            EspError::Failed { opcode, error } => write!(
+                f,
+                "command 0x{:02X} failed with error 0x{:02X}",
+                opcode, error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EspError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EspError::Slip(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SlipError> for EspError {
+    fn from(err: SlipError) -> Self {
+        EspError::Slip(err)
+    }
+}
+
+/// Command sent to the bootloader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request<'a> {
+    /// Begin writing `erase_size` bytes of flash at `offset` in `packets`
+    /// packets of `packet_size` bytes
+    FlashBegin {
+        erase_size: u32,
+        packets: u32,
+        packet_size: u32,
+        offset: u32,
+    },
+    /// Write packet number `seq` of a flash download
+    FlashData { seq: u32, data: &'a [u8] },
+    /// Finish a flash download and optionally reboot into the firmware
+    FlashEnd { reboot: bool },
+    /// Begin downloading `size` bytes to RAM at `offset` in `blocks` blocks
+    /// of `block_size` bytes
+    MemBegin {
+        size: u32,
+        blocks: u32,
+        block_size: u32,
+        offset: u32,
+    },
+    /// Finish a RAM download and optionally jump to `entry`
+    MemEnd { execute: bool, entry: u32 },
+    /// Write block number `seq` of a RAM download
+    MemData { seq: u32, data: &'a [u8] },
+    /// Synchronise with the bootloader's baud rate detection
+    Sync,
+    /// Write `value` to the register at `address` under `mask`, then wait
+    /// `delay_us` microseconds
+    WriteReg {
+        address: u32,
+        value: u32,
+        mask: u32,
+        delay_us: u32,
+    },
+    /// Read the register at `address`; the value is returned in
+    /// [`Response::value`]
+    ReadReg { address: u32 },
+    /// Attach the SPI flash with the given pin configuration (0 for the
+    /// default pins)
+    SpiAttach { pins: u32 },
+    /// Switch to `baud` from `prior_baud` (0 when talking to the ROM)
+    ChangeBaudrate { baud: u32, prior_baud: u32 },
+}
+
+impl<'a> Request<'a> {
+    /// Returns the command opcode (see [`opcode`])
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Request::FlashBegin { .. } => opcode::FLASH_BEGIN,
+            Request::FlashData { .. } => opcode::FLASH_DATA,
+            Request::FlashEnd { .. } => opcode::FLASH_END,
+            Request::MemBegin { .. } => opcode::MEM_BEGIN,
+            Request::MemEnd { .. } => opcode::MEM_END,
+            Request::MemData { .. } => opcode::MEM_DATA,
+            Request::Sync => opcode::SYNC,
+            Request::WriteReg { .. } => opcode::WRITE_REG,
+            Request::ReadReg { .. } => opcode::READ_REG,
+            Request::SpiAttach { .. } => opcode::SPI_ATTACH,
+            Request::ChangeBaudrate { .. } => opcode::CHANGE_BAUDRATE,
+        }
+    }
+
+    /// Returns the header checksum: the payload checksum for `FLASH_DATA`
+    /// and `MEM_DATA`, and zero for every other command
+    pub fn checksum(&self) -> u32 {
+        match self {
+            Request::FlashData { data, .. } | Request::MemData { data, .. } => checksum(data),
+            _ => 0u32,
+        }
+    }
+
+    /// Returns the packet carried in a SLIP frame, or
+    /// [`EspError::TooLong`] if the data does not fit a packet
+    pub fn to_packet(&self) -> Result<Vec<u8>, EspError> {
+        let words: Vec<u32> = match *self {
+            Request::FlashBegin {
+                erase_size,
+                packets,
+                packet_size,
+                offset,
+            } => vec![erase_size, packets, packet_size, offset],
+            Request::FlashData { seq, data } | Request::MemData { seq, data } => {
+                vec![data.len() as u32, seq, 0u32, 0u32]
+            }
+            Request::FlashEnd { reboot } => vec![u32::from(!reboot)],
+            Request::MemBegin {
+                size,
+                blocks,
+                block_size,
+                offset,
+            } => vec![size, blocks, block_size, offset],
+            Request::MemEnd { execute, entry } => vec![u32::from(!execute), entry],
+            Request::Sync => Vec::new(),
+            Request::WriteReg {
+                address,
+                value,
+                mask,
+                delay_us,
+            } => vec![address, value, mask, delay_us],
+            Request::ReadReg { address } => vec![address],
+            Request::SpiAttach { pins } => vec![pins, 0u32],
+            Request::ChangeBaudrate { baud, prior_baud } => vec![baud, prior_baud],
+        };
+        let payload: &[u8] = match self {
+            Request::FlashData { data, .. } | Request::MemData { data, .. } => data,
+            Request::Sync => &SYNC_DATA,
+            _ => &[],
+        };
+        let len = words.len() * 4 + payload.len();
+        let mut packet = Vec::with_capacity(HEADER_LEN + len);
+
+        packet.extend_from_slice(&[REQUEST, self.opcode()]);
+        packet.extend_from_slice(&data_len(len)?.to_le_bytes());
+        packet.extend_from_slice(&self.checksum().to_le_bytes());
+
+        for word in words.iter() {
+            packet.extend_from_slice(&word.to_le_bytes());
+        }
+
+        packet.extend_from_slice(payload);
+        Ok(packet)
+    }
+
+    /// Encodes the request in a SLIP frame and forwards it to the sink.
+    ///
+    /// Returns the number of bytes written to the sink. A request whose data
+    /// does not fit a packet fails with `InvalidInput`.
+    pub fn encode(
+        &self,
+        encoder: &mut SlipEncoder,
+        sink: &mut dyn Write,
+    ) -> std::io::Result<usize> {
+        let packet = self
+            .to_packet()
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+
+        encoder.encode(&packet, sink)
+    }
+
+    /// Parses a request packet, e.g. to emulate the bootloader.
+    ///
+    /// The data of `FLASH_DATA` and `MEM_DATA` is borrowed from `packet`; its
+    /// checksum is not verified, as the bootloader reports a mismatch in the
+    /// response instead.
+    pub fn parse(packet: &'a [u8]) -> Result<Self, EspError> {
+        let malformed = EspError::Malformed { len: packet.len() };
+
+        if packet.len() < HEADER_LEN {
+            return Err(malformed);
+        }
+
+        if packet[0] != REQUEST {
+            return Err(EspError::Direction(packet[0]));
+        }
+
+        let len = usize::from(u16::from_le_bytes([packet[2], packet[3]]));
+        let body = &packet[HEADER_LEN..];
+
+        if body.len() != len {
+            return Err(malformed);
+        }
+
+        let word = |index: usize| {
+            read_u32(body, index * 4).ok_or(EspError::Malformed { len: packet.len() })
+        };
+        let request = match packet[1] {
+            opcode::FLASH_BEGIN => Request::FlashBegin {
+                erase_size: word(0)?,
+                packets: word(1)?,
+                packet_size: word(2)?,
+                offset: word(3)?,
+            },
+            opcode::FLASH_DATA | opcode::MEM_DATA => {
+                let data = body.get(16..).ok_or(malformed)?;
+
+                if word(0)? as usize != data.len() {
+                    return Err(EspError::Malformed { len: packet.len() });
+                }
+
+                if packet[1] == opcode::FLASH_DATA {
+                    Request::FlashData {
+                        seq: word(1)?,
+                        data,
+                    }
+                } else {
+                    Request::MemData {
+                        seq: word(1)?,
+                        data,
+                    }
+                }
+            }
+            opcode::FLASH_END => Request::FlashEnd {
+                reboot: word(0)? == 0,
+            },
+            opcode::MEM_BEGIN => Request::MemBegin {
+                size: word(0)?,
+                blocks: word(1)?,
+                block_size: word(2)?,
+                offset: word(3)?,
+            },
+            opcode::MEM_END => Request::MemEnd {
+                execute: word(0)? == 0,
+                entry: word(1)?,
+            },
+            opcode::SYNC if body == SYNC_DATA => Request::Sync,
+            opcode::SYNC => return Err(malformed),
+            opcode::WRITE_REG => Request::WriteReg {
+                address: word(0)?,
+                value: word(1)?,
+                mask: word(2)?,
+                delay_us: word(3)?,
+            },
+            opcode::READ_REG => Request::ReadReg { address: word(0)? },
+            opcode::SPI_ATTACH => Request::SpiAttach { pins: word(0)? },
+            opcode::CHANGE_BAUDRATE => Request::ChangeBaudrate {
+                baud: word(0)?,
+                prior_baud: word(1)?,
+            },
+            opcode => return Err(EspError::UnknownCommand(opcode)),
+        };
+
+        Ok(request)
+    }
+}
+
+/// Reply from the bootloader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    /// Opcode of the command answered
+    pub opcode: u8,
+    /// Result value, e.g. the register read by `READ_REG`
+    pub value: u32,
+    /// Data preceding the status bytes
+    pub data: Vec<u8>,
+    /// Zero on success
+    pub status: u8,
+    /// Error code when `status` is not zero
+    pub error: u8,
+}
+
+impl Response {
+    /// Parses a response packet ending with `status_len` status bytes (see
+    /// [`ROM_STATUS_LEN`] and [`STUB_STATUS_LEN`])
+    pub fn parse(packet: &[u8], status_len: usize) -> Result<Self, EspError> {
+        let malformed = EspError::Malformed { len: packet.len() };
+
+        if packet.len() < HEADER_LEN + status_len || status_len < 2 {
+            return Err(malformed);
+        }
+
+        if packet[0] != RESPONSE {
+            return Err(EspError::Direction(packet[0]));
+        }
+
+        let len = usize::from(u16::from_le_bytes([packet[2], packet[3]]));
+        let body = &packet[HEADER_LEN..];
+
+        if body.len() != len {
+            return Err(malformed);
+        }
+
+        let (data, status) = body.split_at(len - status_len);
+
+        Ok(Response {
+            opcode: packet[1],
+            value: read_u32(packet, 4).unwrap_or_default(),
+            data: data.to_vec(),
+            status: status[0],
+            error: status[1],
+        })
+    }
+
+    /// Decodes the next response frame from `source`, skipping frames that
+    /// are not responses such as the echo of a request on a looped-back line
+    pub fn read(
+        decoder: &mut SlipDecoder,
+        source: &mut dyn Read,
+        status_len: usize,
+    ) -> Result<Self, EspError> {
+        let mut packet = Vec::new();
+
+        loop {
+            packet.clear();
+            decoder.decode(source, &mut packet)?;
+
+            match Self::parse(&packet, status_len) {
+                Err(EspError::Direction(_)) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the packet carried in a SLIP frame, with `status_len` status
+    /// bytes, or [`EspError::TooLong`] if the data does not fit a packet
+    pub fn to_packet(&self, status_len: usize) -> Result<Vec<u8>, EspError> {
+        let len = self.data.len() + status_len;
+        let mut packet = Vec::with_capacity(HEADER_LEN + len);
+
+        packet.extend_from_slice(&[RESPONSE, self.opcode]);
+        packet.extend_from_slice(&data_len(len)?.to_le_bytes());
+        packet.extend_from_slice(&self.value.to_le_bytes());
+        packet.extend_from_slice(&self.data);
+        packet.extend_from_slice(&[self.status, self.error]);
+        packet.resize(HEADER_LEN + len, 0u8);
+        Ok(packet)
+    }
+
+    /// Checks that this is a successful response to `request`
+    pub fn check(self, request: &Request<'_>) -> Result<Self, EspError> {
+        if self.opcode != request.opcode() {
+            return Err(EspError::UnexpectedResponse {
+                expected: request.opcode(),
+                received: self.opcode,
+            });
+        }
+
+        if self.status != 0 {
+            return Err(EspError::Failed {
+                opcode: self.opcode,
+                error: self.error,
+            });
+        }
+
+        Ok(self)
+    }
+}
+
+/// Computes the checksum of `FLASH_DATA` and `MEM_DATA` payloads
+pub fn checksum(data: &[u8]) -> u32 {
+    u32::from(data.iter().fold(CHECKSUM_SEED, |sum, byte| sum ^ byte))
+}
+
+fn data_len(len: usize) -> Result<u16, EspError> {
+    u16::try_from(len).map_err(|_| EspError::TooLong { len })
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    Some(read_u32_le(buf.get(offset..offset + 4)?, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SYNC request as sent by esptool
+    const SYNC_TRACE: [u8; 46] = [
+        0xc0, 0x00, 0x08, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x07, 0x12, 0x20, 0x55, 0x55,
+        0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+        0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+        0xc0,
+    ];
+
+    /// SYNC response from an ESP32 ROM
+    const SYNC_RESPONSE_TRACE: [u8; 14] = [
+        0xc0, 0x01, 0x08, 0x04, 0x00, 0x07, 0x07, 0x12, 0x20, 0x00, 0x00, 0x00, 0x00, 0xc0,
+    ];
+
+    /// READ_REG of the chip detection magic register at 0x40001000
+    const READ_REG_TRACE: [u8; 14] = [
+        0xc0, 0x00, 0x0a, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x40, 0xc0,
+    ];
+
+    /// READ_REG response from an ESP32 ROM with the ESP32 magic value
+    const READ_REG_RESPONSE_TRACE: [u8; 14] = [
+        0xc0, 0x01, 0x0a, 0x04, 0x00, 0x83, 0x1d, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0,
+    ];
+
+    /// FLASH_BEGIN of 0x4000 bytes at 0x1000 in 4 packets of 0x1000 bytes
+    const FLASH_BEGIN_TRACE: [u8; 26] = [
+        0xc0, 0x00, 0x02, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x04, 0x00,
+        0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0xc0,
+    ];
+
+    /// FLASH_DATA packet 1 with a payload that needs escaping
+    const FLASH_DATA_TRACE: [u8; 32] = [
+        0xc0, 0x00, 0x03, 0x14, 0x00, 0xf7, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xdb, 0xdc, 0xdb, 0xdd, 0x01,
+        0x02, 0xc0,
+    ];
+
+    /// FLASH_DATA response from the ESP8266 ROM reporting a checksum error
+    const FLASH_DATA_FAILED_TRACE: [u8; 12] = [
+        0xc0, 0x01, 0x03, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x07, 0xc0,
+    ];
+
+    fn encode(request: &Request<'_>) -> Vec<u8> {
+        let mut output = Vec::new();
+        request
+            .encode(&mut SlipEncoder::default(), &mut output)
+            .unwrap();
+        output
+    }
+
+    fn decode(trace: &[u8]) -> Vec<u8> {
+        let mut packet = Vec::new();
+        SlipDecoder::new()
+            .decode(&mut &trace[..], &mut packet)
+            .unwrap();
+        packet
+    }
+
+    #[test]
+    fn sync() {
+        assert_eq!(&SYNC_TRACE[..], encode(&Request::Sync));
+        assert_eq!(Request::Sync, Request::parse(&decode(&SYNC_TRACE)).unwrap());
+
+        let response = Response::read(
+            &mut SlipDecoder::new(),
+            &mut &SYNC_RESPONSE_TRACE[..],
+            ROM_STATUS_LEN,
+        )
+        .unwrap()
+        .check(&Request::Sync)
+        .unwrap();
+        assert_eq!(0x2012_0707, response.value);
+        assert!(response.data.is_empty());
+        assert_eq!(
+            decode(&SYNC_RESPONSE_TRACE),
+            response.to_packet(ROM_STATUS_LEN).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_reg() {
+        let request = Request::ReadReg {
+            address: 0x4000_1000,
+        };
+        assert_eq!(&READ_REG_TRACE[..], encode(&request));
+        assert_eq!(request, Request::parse(&decode(&READ_REG_TRACE)).unwrap());
+
+        let response = Response::parse(&decode(&READ_REG_RESPONSE_TRACE), ROM_STATUS_LEN)
+            .unwrap()
+            .check(&request)
+            .unwrap();
+        assert_eq!(0x00f0_1d83, response.value);
+    }
+
+    #[test]
+    fn write_reg() {
+        let request = Request::WriteReg {
+            address: 0x3ff4_8000,
+            value: 0x50d8_3aa1,
+            mask: 0xffff_ffff,
+            delay_us: 0,
+        };
+        let packet = request.to_packet().unwrap();
+        assert_eq!(&[0x00, 0x09, 0x10, 0x00], &packet[..4]);
+        assert_eq!(
+            &[0x00, 0x80, 0xf4, 0x3f, 0xa1, 0x3a, 0xd8, 0x50],
+            &packet[8..16]
+        );
+        assert_eq!(request, Request::parse(&packet).unwrap());
+    }
+
+    #[test]
+    fn flash_download() {
+        let begin = Request::FlashBegin {
+            erase_size: 0x4000,
+            packets: 4,
+            packet_size: 0x1000,
+            offset: 0x1000,
+        };
+        assert_eq!(&FLASH_BEGIN_TRACE[..], encode(&begin));
+        assert_eq!(begin, Request::parse(&decode(&FLASH_BEGIN_TRACE)).unwrap());
+
+        let data = Request::FlashData {
+            seq: 1,
+            data: &[0xc0, 0xdb, 0x01, 0x02],
+        };
+        assert_eq!(0xf7, data.checksum());
+        assert_eq!(&FLASH_DATA_TRACE[..], encode(&data));
+        let packet = decode(&FLASH_DATA_TRACE);
+        assert_eq!(data, Request::parse(&packet).unwrap());
+
+        let err = Response::parse(&decode(&FLASH_DATA_FAILED_TRACE), STUB_STATUS_LEN)
+            .unwrap()
+            .check(&data)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            EspError::Failed {
+                opcode: opcode::FLASH_DATA,
+                error: 0x07
+            }
+        ));
+
+        let end = Request::FlashEnd { reboot: true };
+        assert_eq!(&[0x00, 0x00, 0x00, 0x00], &end.to_packet().unwrap()[8..]);
+        assert_eq!(end, Request::parse(&end.to_packet().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn mem_download() {
+        let requests = [
+            Request::MemBegin {
+                size: 0x100,
+                blocks: 1,
+                block_size: 0x1800,
+                offset: 0x4009_0000,
+            },
+            Request::MemData {
+                seq: 0,
+                data: &[0x11, 0x22, 0x33],
+            },
+            Request::MemEnd {
+                execute: true,
+                entry: 0x4009_0040,
+            },
+            Request::SpiAttach { pins: 0 },
+            Request::ChangeBaudrate {
+                baud: 921_600,
+                prior_baud: 0,
+            },
+        ];
+
+        for request in requests.iter() {
+            assert_eq!(
+                *request,
+                Request::parse(&request.to_packet().unwrap()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn read_skips_requests() {
+        // a looped-back line echoes the request before the response arrives
+        let mut trace = READ_REG_TRACE.to_vec();
+        trace.extend_from_slice(&READ_REG_RESPONSE_TRACE);
+
+        let response = Response::read(
+            &mut SlipDecoder::new(),
+            &mut trace.as_slice(),
+            ROM_STATUS_LEN,
+        )
+        .unwrap();
+        assert_eq!(opcode::READ_REG, response.opcode);
+    }
+
+    #[test]
+    fn malformed() {
+        let mut packet = Request::ReadReg { address: 0 }.to_packet().unwrap();
+        packet.pop();
+        assert!(matches!(
+            Request::parse(&packet),
+            Err(EspError::Malformed { len: 11 })
+        ));
+        assert!(matches!(
+            Request::parse(&[0x00, 0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            Err(EspError::UnknownCommand(0x42))
+        ));
+        assert!(matches!(
+            Response::parse(&decode(&SYNC_RESPONSE_TRACE), 8),
+            Err(EspError::Malformed { len: 12 })
+        ));
+    }
+
+    #[test]
+    fn too_long() {
+        let data = vec![0u8; 0x1_0000];
+        let request = Request::FlashData {
+            seq: 0,
+            data: &data,
+        };
+        assert!(matches!(
+            request.to_packet(),
+            Err(EspError::TooLong { len: 0x1_0010 })
+        ));
+        let err = request
+            .encode(&mut SlipEncoder::default(), &mut Vec::new())
+            .unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+
+        let response = Response {
+            opcode: opcode::READ_REG,
+            value: 0,
+            data: data[..0xffff].to_vec(),
+            status: 0,
+            error: 0,
+        };
+        assert!(matches!(
+            response.to_packet(STUB_STATUS_LEN),
+            Err(EspError::TooLong { len: 0x1_0001 })
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{SlipDecoder, SlipError};
+
+/// Iterator over the SLIP frames contained in a buffer
+///
+/// Frames are decoded in place and yielded as mutable sub-slices of the
+/// caller's buffer, so no copies or allocations are made. Iteration stops at
+/// the first incomplete frame, which remains available undecoded through
+/// [`remainder`].
+///
+/// [`remainder`]: Frames::remainder
+#[derive(Debug)]
+pub struct Frames<'a> {
+    buf: &'a mut [u8],
+    decoder: SlipDecoder,
+}
+
+impl<'a> Frames<'a> {
+    /// Creates an iterator over the frames in `buf`
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self {
+            buf,
+            decoder: SlipDecoder::new(),
+        }
+    }
+
+    /// Returns the encoded bytes following the last complete frame
+    pub fn remainder(self) -> &'a mut [u8] {
+        self.buf
+    }
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Result<&'a mut [u8], SlipError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let decoded = self.decoder.decode_in_place(self.buf);
+        let (head, tail) = core::mem::take(&mut self.buf).split_at_mut(decoded.consumed);
+
+        self.buf = tail;
+
+        match decoded.frame {
+            Ok(Some(len)) => Some(Ok(&mut head[..len])),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compound_frames() {
+        let mut input = [
+            0xc0, 0x01, 0x02, 0xc0, 0xc0, 0x03, 0xdb, 0xdd, 0xc0, 0x04, 0xdb,
+        ];
+
+        let mut frames = Frames::new(&mut input);
+        assert_eq!(&[0x01, 0x02], frames.next().unwrap().unwrap());
+        assert_eq!(&[0x03, 0xdb], frames.next().unwrap().unwrap());
+        assert!(frames.next().is_none());
+        assert_eq!(&[0x04, 0xdb], frames.remainder());
+    }
+
+    #[test]
+    fn framing_error_resync() {
+        let mut input = [0xc0, 0x01, 0xdb, 0x02, 0x03, 0xc0, 0x04, 0xc0];
+
+        let mut frames = Frames::new(&mut input);
+        assert!(matches!(
+            frames.next().unwrap(),
+            Err(SlipError::InvalidEscape {
+                byte: 0x02,
+                offset: 3
+            })
+        ));
+        assert_eq!(&[0x04], frames.next().unwrap().unwrap());
+        assert!(frames.next().is_none());
+        assert!(frames.remainder().is_empty());
+    }
+}
@@ -0,0 +1,1104 @@
+//! `BytesMut` framing engine shared by the `tokio` and `aio` codecs
+//!
+//! Both front-ends wrap [`FrameDecoder`] and [`FrameEncoder`] and only adapt
+//! them to their trait family, so limits, policies and statistics are
+//! implemented once here. Their `SlipCodec` types are instances of the generic
+//! [`SlipCodec`], which only lacks the codec trait impls.
+
+use crate::{EofPolicy, EscapePolicy, FlushPolicy, SlipError, SlipStats, MAX_PACKET_SIZE};
+use bytes::{Buf, BufMut, BytesMut};
+use core::marker::PhantomData;
+
+/// Incremental SLIP decoder over a `BytesMut` source
+///
+/// The encoded bytes of an incomplete frame are left in the source buffer
+/// until the frame is complete, so that `decode_eof` can apply the
+/// configured [`EofPolicy`] when the input ends in the middle of a frame.
+#[derive(Debug)]
+pub(crate) struct FrameDecoder {
+    buf: BytesMut,
+    capacity: usize,
+    eof_policy: EofPolicy,
+    inner: crate::SlipDecoder,
+    scanned: usize,
+}
+
+impl FrameDecoder {
+    pub(crate) fn new(capacity: usize, mut inner: crate::SlipDecoder) -> Self {
+        let max_len = inner
+            .max_len()
+            .map_or(capacity, |max_len| max_len.min(capacity));
+
+        inner.set_max_len(Some(max_len));
+
+        Self {
+            buf: BytesMut::with_capacity(capacity),
+            capacity: max_len,
+            eof_policy: EofPolicy::Discard,
+            inner,
+            scanned: 0usize,
+        }
+    }
+
+    pub(crate) fn set_eof_policy(&mut self, eof_policy: EofPolicy) {
+        self.eof_policy = eof_policy;
+    }
+
+    pub(crate) fn stats(&self) -> &SlipStats {
+        self.inner.stats()
+    }
+
+    pub(crate) fn reset_stats(&mut self) {
+        self.inner.reset_stats();
+    }
+
+    /// Drops the encoded bytes examined so far along with any partial frame
+    fn discard(&mut self, src: &mut BytesMut) {
+        src.advance(self.scanned);
+        self.scanned = 0usize;
+        self.buf.clear();
+    }
+
+    pub(crate) fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, SlipError> {
+        loop {
+            // frames longer than the capacity are rejected before they
+            // could need more room than this
+            let start = self.buf.len();
+            let room = (src.len() - self.scanned + 1).min(self.capacity - start + 2);
+
+            self.buf.resize(start + room, 0u8);
+
+            let decoded = self
+                .inner
+                .decode_slice(&src[self.scanned..], &mut self.buf[start..]);
+
+            self.buf.truncate(start + decoded.produced);
+            self.scanned += decoded.consumed;
+
+            match decoded.frame {
+                Ok(Some(len)) => {
+                    src.advance(self.scanned);
+                    self.scanned = 0usize;
+
+                    return Ok(Some(self.buf.split_to(len)));
+                }
+                Ok(None) => {
+                    if !self.inner.in_frame() {
+                        self.discard(src);
+                    }
+
+                    return Ok(None);
+                }
+                Err(SlipError::OversizedPacket { .. }) => {
+                    self.discard(src);
+                }
+                Err(SlipError::InvalidEscape { .. })
+                    if self.inner.escape_policy() == EscapePolicy::DropFrame =>
+                {
+                    self.discard(src);
+                }
+                Err(e) => {
+                    self.discard(src);
+
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, SlipError> {
+        if let Some(frame) = self.decode(src)? {
+            return Ok(Some(frame));
+        }
+
+        if !self.inner.in_frame() {
+            return Ok(None);
+        }
+
+        let len = self.inner.partial_len();
+
+        self.inner.reset();
+        src.advance(self.scanned);
+        self.scanned = 0usize;
+
+        match self.eof_policy {
+            EofPolicy::Emit if len > 0 => Ok(Some(self.buf.split_to(len))),
+            EofPolicy::Error => {
+                self.buf.clear();
+
+                Err(SlipError::Truncated { len })
+            }
+            _ => {
+                self.buf.clear();
+
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new(MAX_PACKET_SIZE, Default::default())
+    }
+}
+
+/// SLIP encoder into a `BytesMut` destination
+///
+/// The output buffer is in memory, so the inner encoder never flushes.
+#[derive(Debug)]
+pub(crate) struct FrameEncoder {
+    inner: crate::SlipEncoder,
+    empty_frames: bool,
+}
+
+impl FrameEncoder {
+    pub(crate) fn new(begin_with_end: bool) -> Self {
+        Self {
+            inner: crate::SlipEncoder::builder()
+                .begin_with_end(begin_with_end)
+                .flush_policy(FlushPolicy::Never)
+                .build(),
+            empty_frames: false,
+        }
+    }
+
+    /// Encodes empty items as explicit empty frames, a single `END` token
+    pub(crate) fn set_empty_frames(&mut self, empty_frames: bool) {
+        self.empty_frames = empty_frames;
+    }
+
+    pub(crate) fn encode(&mut self, item: &[u8], dst: &mut BytesMut) -> std::io::Result<()> {
+        self.encode_parts(&[item], dst)
+    }
+
+    /// Encodes the concatenation of `parts` as a single frame
+    pub(crate) fn encode_parts(
+        &mut self,
+        parts: &[&[u8]],
+        dst: &mut BytesMut,
+    ) -> std::io::Result<()> {
+        if self.empty_frames && parts.iter().all(|part| part.is_empty()) {
+            return self.inner.encode_empty(&mut dst.writer()).map(|_| ());
+        }
+
+        dst.reserve(self.inner.frame_len(parts));
+
+        self.inner
+            .encode_parts(parts, &mut dst.writer())
+            .map(|_| ())
+    }
+}
+
+impl Default for FrameEncoder {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+/// SLIP codec of either front-end, decoding frames of type `F`
+#[derive(Debug)]
+pub struct SlipCodec<F> {
+    decoder: FrameDecoder,
+    encoder: FrameEncoder,
+    frame: PhantomData<fn() -> F>,
+}
+
+impl<F> SlipCodec<F> {
+    pub fn new() -> Self {
+        Self {
+            decoder: FrameDecoder::default(),
+            encoder: FrameEncoder::default(),
+            frame: PhantomData,
+        }
+    }
+
+    pub fn builder() -> SlipCodecBuilder<Self> {
+        SlipCodecBuilder::new()
+    }
+
+    /// Creates a codec from a configuration, e.g. one read from a file
+    pub fn from_config(config: SlipCodecConfig) -> Self {
+        SlipCodecBuilder::from(config).build()
+    }
+
+    /// Returns the link quality counters of the decoder
+    pub fn stats(&self) -> &SlipStats {
+        self.decoder.stats()
+    }
+
+    /// Clears the link quality counters of the decoder
+    pub fn reset_stats(&mut self) {
+        self.decoder.reset_stats();
+    }
+
+    /// Returns the engine halves for layered codecs
+    #[cfg(any(feature = "checksum", feature = "cslip"))]
+    pub(crate) fn engine_mut(&mut self) -> (&mut FrameDecoder, &mut FrameEncoder) {
+        (&mut self.decoder, &mut self.encoder)
+    }
+
+    pub(crate) fn encode_item(&mut self, item: &[u8], dst: &mut BytesMut) -> Result<(), SlipError> {
+        self.encoder.encode(item, dst).map_err(SlipError::ReadError)
+    }
+}
+
+impl<F: From<BytesMut>> SlipCodec<F> {
+    pub(crate) fn decode_item(&mut self, src: &mut BytesMut) -> Result<Option<F>, SlipError> {
+        self.decoder.decode(src).map(|frame| frame.map(F::from))
+    }
+
+    pub(crate) fn decode_item_eof(&mut self, src: &mut BytesMut) -> Result<Option<F>, SlipError> {
+        self.decoder.decode_eof(src).map(|frame| frame.map(F::from))
+    }
+}
+
+impl<F> Default for SlipCodec<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Transformation of frame payloads layered over the engine, shared by the
+/// layered codecs of both front-ends
+#[cfg(any(feature = "checksum", feature = "cslip"))]
+pub(crate) trait FrameLayer {
+    /// Restores the payload carried by a decoded `frame`, or returns `None`
+    /// to skip a frame that cannot be restored
+    fn restore(&mut self, frame: BytesMut) -> Option<BytesMut>;
+
+    /// Encodes `item` through `encoder` into `dst`
+    fn encode(
+        &mut self,
+        item: &[u8],
+        encoder: &mut FrameEncoder,
+        dst: &mut BytesMut,
+    ) -> std::io::Result<()>;
+
+    /// Decodes the next payload, skipping frames that cannot be restored
+    fn decode(
+        &mut self,
+        decoder: &mut FrameDecoder,
+        src: &mut BytesMut,
+    ) -> Result<Option<BytesMut>, SlipError> {
+        while let Some(frame) = decoder.decode(src)? {
+            if let Some(payload) = self.restore(frame) {
+                return Ok(Some(payload));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Decodes the next payload at the end of the input
+    fn decode_eof(
+        &mut self,
+        decoder: &mut FrameDecoder,
+        src: &mut BytesMut,
+    ) -> Result<Option<BytesMut>, SlipError> {
+        if let Some(payload) = self.decode(decoder, src)? {
+            return Ok(Some(payload));
+        }
+
+        match decoder.decode_eof(src)? {
+            Some(frame) => Ok(self.restore(frame)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Verifies and strips the checksum of each frame, skipping and counting
+/// frames that fail verification
+#[cfg(feature = "checksum")]
+#[derive(Debug)]
+pub(crate) struct ChecksumLayer<C> {
+    checksum: C,
+    errors: u64,
+}
+
+#[cfg(feature = "checksum")]
+impl<C: crate::checksum::Checksum> ChecksumLayer<C> {
+    pub(crate) fn new(checksum: C) -> Self {
+        Self {
+            checksum,
+            errors: 0u64,
+        }
+    }
+
+    /// Returns the number of frames skipped for a checksum mismatch or for
+    /// being too short to hold a checksum
+    pub(crate) fn errors(&self) -> u64 {
+        self.errors
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl<C: crate::checksum::Checksum> FrameLayer for ChecksumLayer<C> {
+    fn restore(&mut self, mut frame: BytesMut) -> Option<BytesMut> {
+        match self.checksum.verify(&frame) {
+            Ok(len) => {
+                frame.truncate(len);
+
+                Some(frame)
+            }
+            Err(_) => {
+                self.errors += 1;
+
+                None
+            }
+        }
+    }
+
+    fn encode(
+        &mut self,
+        item: &[u8],
+        encoder: &mut FrameEncoder,
+        dst: &mut BytesMut,
+    ) -> std::io::Result<()> {
+        let trailer = self.checksum.trailer(item);
+
+        encoder.encode_parts(&[item, &trailer[..C::LEN]], dst)
+    }
+}
+
+/// Compresses TCP/IP headers with RFC 1144, skipping frames that cannot be
+/// decompressed
+#[cfg(feature = "cslip")]
+#[derive(Debug, Default)]
+pub(crate) struct CslipLayer {
+    compressor: crate::cslip::Compressor,
+    decompressor: crate::cslip::Decompressor,
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "cslip")]
+impl CslipLayer {
+    pub(crate) fn new(
+        compressor: crate::cslip::Compressor,
+        decompressor: crate::cslip::Decompressor,
+    ) -> Self {
+        Self {
+            compressor,
+            decompressor,
+            buf: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "cslip")]
+impl FrameLayer for CslipLayer {
+    fn restore(&mut self, frame: BytesMut) -> Option<BytesMut> {
+        // TCP retransmissions resynchronise the link after a bad frame
+        match self.decompressor.decompress(&frame, &mut self.buf) {
+            Ok(_) => Some(BytesMut::from(&self.buf[..])),
+            Err(_) => None,
+        }
+    }
+
+    fn encode(
+        &mut self,
+        item: &[u8],
+        encoder: &mut FrameEncoder,
+        dst: &mut BytesMut,
+    ) -> std::io::Result<()> {
+        self.compressor.compress(item, &mut self.buf);
+
+        encoder.encode(&self.buf, dst)
+    }
+}
+
+/// Configuration of the `SlipCodec` of either front-end
+///
+/// With the `serde` feature the configuration can be deserialized, e.g. from
+/// a service configuration file. Missing fields take their default values.
+///
+/// There is no flush setting: the codec encodes into the buffer of the
+/// `Framed` sink, which flushes the transport when it is flushed itself or
+/// its buffer fills up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct SlipCodecConfig {
+    /// Begin every encoded frame with an `END` token
+    pub begin_with_end: bool,
+    /// Size of the decoding buffer and limit on the decoded frame length
+    pub capacity: usize,
+    /// Limit on the decoded frame length below the capacity
+    pub max_len: Option<usize>,
+    /// Policy for invalid escape sequences
+    pub escape_policy: EscapePolicy,
+    /// Policy for a frame left incomplete at the end of the input
+    pub eof_policy: EofPolicy,
+    /// Deliver zero-length frames and send empty items as a single `END`
+    pub empty_frames: bool,
+}
+
+impl Default for SlipCodecConfig {
+    fn default() -> Self {
+        Self {
+            begin_with_end: true,
+            capacity: MAX_PACKET_SIZE,
+            max_len: None,
+            escape_policy: EscapePolicy::DropFrame,
+            eof_policy: EofPolicy::Discard,
+            empty_frames: false,
+        }
+    }
+}
+
+/// Builder for the `SlipCodec` of either front-end
+pub struct SlipCodecBuilder<C> {
+    config: SlipCodecConfig,
+    codec: PhantomData<fn() -> C>,
+}
+
+impl<C> SlipCodecBuilder<C> {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces every setting with those of `config`
+    pub fn config(self, config: SlipCodecConfig) -> Self {
+        Self { config, ..self }
+    }
+
+    /// Begins every encoded frame with an `END` token, which flushes any
+    /// line noise received by the peer before the frame; enabled by default
+    ///
+    /// This is independent of [`empty_frames`]: a peer that delivers empty
+    /// frames sees each leading `END` as one, so disable this when talking to
+    /// such a peer.
+    ///
+    /// [`empty_frames`]: SlipCodecBuilder::empty_frames
+    pub fn begin_with_end(mut self, begin_with_end: bool) -> Self {
+        self.config.begin_with_end = begin_with_end;
+        self
+    }
+
+    /// Sets the size of the decoding buffer, which also limits the decoded
+    /// frame length; [`MAX_PACKET_SIZE`] by default
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.config.capacity = capacity;
+        self
+    }
+
+    /// Limits decoded frames to `max_len` bytes; the smaller of this and
+    /// the capacity applies
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.config.max_len = Some(max_len);
+        self
+    }
+
+    /// Sets the policy for invalid escape sequences,
+    /// [`EscapePolicy::DropFrame`] by default
+    pub fn escape_policy(mut self, escape_policy: EscapePolicy) -> Self {
+        self.config.escape_policy = escape_policy;
+        self
+    }
+
+    /// Sets the policy for a frame left incomplete at the end of the input
+    pub fn eof_policy(mut self, eof_policy: EofPolicy) -> Self {
+        self.config.eof_policy = eof_policy;
+        self
+    }
+
+    /// Delivers zero-length frames as empty items and encodes empty items
+    /// as explicit empty frames, e.g. for keepalives
+    ///
+    /// This is independent of [`begin_with_end`], which stays enabled by
+    /// default; a peer with empty frames enabled decodes each leading `END`
+    /// as an extra empty frame unless it is disabled. See
+    /// [`SlipDecoderBuilder::empty_frames`] about peers that send one.
+    ///
+    /// [`begin_with_end`]: SlipCodecBuilder::begin_with_end
+    /// [`SlipDecoderBuilder::empty_frames`]: crate::SlipDecoderBuilder::empty_frames
+    pub fn empty_frames(mut self, empty_frames: bool) -> Self {
+        self.config.empty_frames = empty_frames;
+        self
+    }
+}
+
+impl<F> SlipCodecBuilder<SlipCodec<F>> {
+    pub fn build(self) -> SlipCodec<F> {
+        let config = self.config;
+        let inner = crate::SlipDecoder::builder()
+            .escape_policy(config.escape_policy)
+            .empty_frames(config.empty_frames);
+        let inner = match config.max_len {
+            Some(max_len) => inner.max_len(max_len),
+            None => inner,
+        };
+        let mut decoder = FrameDecoder::new(config.capacity, inner.build());
+
+        let mut encoder = FrameEncoder::new(config.begin_with_end);
+
+        decoder.set_eof_policy(config.eof_policy);
+        encoder.set_empty_frames(config.empty_frames);
+
+        SlipCodec {
+            decoder,
+            encoder,
+            frame: PhantomData,
+        }
+    }
+}
+
+impl<C> Default for SlipCodecBuilder<C> {
+    fn default() -> Self {
+        Self::from(SlipCodecConfig::default())
+    }
+}
+
+impl<C> From<SlipCodecConfig> for SlipCodecBuilder<C> {
+    fn from(config: SlipCodecConfig) -> Self {
+        Self {
+            config,
+            codec: PhantomData,
+        }
+    }
+}
+
+impl<C> Clone for SlipCodecBuilder<C> {
+    fn clone(&self) -> Self {
+        Self::from(self.config.clone())
+    }
+}
+
+impl<C> core::fmt::Debug for SlipCodecBuilder<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SlipCodecBuilder")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{END, ESC, ESC_END, ESC_ESC};
+
+    /// Codec traits of a front-end under test
+    pub(crate) trait FrontEnd {
+        fn decode_frame(&mut self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, SlipError>;
+        fn decode_frame_eof(&mut self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, SlipError>;
+        fn encode_frame(&mut self, item: &[u8], dst: &mut BytesMut) -> Result<(), SlipError>;
+        /// Reads `input` through the front-end's framed reader, returning the
+        /// frames up to the end of the stream or the first error
+        fn read_frames(self, input: &'static [u8]) -> (Vec<Vec<u8>>, Option<SlipError>);
+    }
+
+    enum Expect {
+        Frame(&'static [u8]),
+        Pending,
+        Error(fn(&SlipError) -> bool),
+    }
+
+    enum Step {
+        /// Appends encoded bytes to the source buffer
+        Feed(&'static [u8]),
+        Decode(Expect),
+        DecodeEof(Expect),
+        /// Number of encoded bytes left in the source buffer
+        Buffered(usize),
+        /// Number of bytes of a partial frame held by the engine
+        Partial(usize),
+        Recoveries(u64),
+        Stats(SlipStats),
+        ResetStats,
+    }
+
+    struct DecodeCase {
+        name: &'static str,
+        capacity: usize,
+        max_len: Option<usize>,
+        escape_policy: EscapePolicy,
+        eof_policy: EofPolicy,
+        empty_frames: bool,
+        steps: Vec<Step>,
+    }
+
+    impl DecodeCase {
+        fn new(name: &'static str, steps: Vec<Step>) -> Self {
+            Self {
+                name,
+                capacity: MAX_PACKET_SIZE,
+                max_len: None,
+                escape_policy: EscapePolicy::DropFrame,
+                eof_policy: EofPolicy::Discard,
+                empty_frames: false,
+                steps,
+            }
+        }
+
+        fn capacity(self, capacity: usize) -> Self {
+            Self { capacity, ..self }
+        }
+
+        fn max_len(self, max_len: usize) -> Self {
+            Self {
+                max_len: Some(max_len),
+                ..self
+            }
+        }
+
+        fn escape_policy(self, escape_policy: EscapePolicy) -> Self {
+            Self {
+                escape_policy,
+                ..self
+            }
+        }
+
+        fn eof_policy(self, eof_policy: EofPolicy) -> Self {
+            Self { eof_policy, ..self }
+        }
+
+        fn empty_frames(self) -> Self {
+            Self {
+                empty_frames: true,
+                ..self
+            }
+        }
+    }
+
+    const INVALID_ESCAPE: [u8; 9] = [0xc0, 0x01, 0xdb, 0x02, 0x03, 0xc0, 0x04, 0x05, 0xc0];
+    const TRUNCATED: [u8; 7] = [0xc0, 0x01, 0x02, 0xc0, 0x03, 0xdb, 0xdc];
+
+    fn decode_cases() -> Vec<DecodeCase> {
+        use Expect::*;
+        use Step::*;
+
+        vec![
+            DecodeCase::new(
+                "empty_decode",
+                vec![Feed(&[0xc0, 0xc0]), Decode(Pending), Buffered(0)],
+            ),
+            DecodeCase::new(
+                "deliver_empty_frames",
+                vec![
+                    // frames sent without a leading END
+                    Feed(&[0xc0, 0x01, 0xc0, 0xc0, 0xdb, 0x02, 0xc0]),
+                    Decode(Frame(&[])),
+                    Decode(Frame(&[0x01])),
+                    Decode(Frame(&[])),
+                    // the END closing a dropped frame is not an empty frame
+                    Decode(Pending),
+                    Buffered(0),
+                    Feed(&[0xc0]),
+                    Decode(Frame(&[])),
+                ],
+            )
+            .empty_frames(),
+            DecodeCase::new(
+                "simple_decode",
+                vec![
+                    Feed(&[0xc0, 0x01, 0x02, 0x03, 0x04, 0x05, 0xc0]),
+                    Decode(Frame(&[0x01, 0x02, 0x03, 0x04, 0x05])),
+                ],
+            ),
+            // [ESC, ESC_END] -> [END]
+            DecodeCase::new(
+                "decode_esc_then_esc_end_sequence",
+                vec![
+                    Feed(&[0xc0, 0x01, 0xdb, 0xdc, 0x03, 0xc0]),
+                    Decode(Frame(&[0x01, 0xc0, 0x03])),
+                ],
+            ),
+            // [ESC, ESC_ESC] -> [ESC]
+            DecodeCase::new(
+                "decode_esc_then_esc_esc_sequence",
+                vec![
+                    Feed(&[0xc0, 0x01, 0xdb, 0xdd, 0x03, 0xc0]),
+                    Decode(Frame(&[0x01, 0xdb, 0x03])),
+                ],
+            ),
+            DecodeCase::new(
+                "multi_part_decode",
+                vec![
+                    Feed(&[0xc0, 0x01, 0x02, 0x03, 0x04, 0x05]),
+                    Decode(Pending),
+                    // the incomplete frame stays buffered for decode_eof;
+                    // 0.4 and earlier consumed it (see CHANGELOG.md)
+                    Buffered(6),
+                    Feed(&[0x05, 0x06, 0x07, 0x08, 0x09, 0xc0]),
+                    Decode(Frame(&[
+                        0x01, 0x02, 0x03, 0x04, 0x05, 0x05, 0x06, 0x07, 0x08, 0x09,
+                    ])),
+                ],
+            ),
+            DecodeCase::new(
+                "drop_invalid_escape_frame",
+                vec![
+                    Feed(&INVALID_ESCAPE),
+                    Decode(Frame(&[0x04, 0x05])),
+                    Recoveries(1),
+                ],
+            ),
+            DecodeCase::new(
+                "pass_invalid_escape_literal",
+                vec![
+                    Feed(&INVALID_ESCAPE),
+                    Decode(Frame(&[0x01, 0x02, 0x03])),
+                    Decode(Frame(&[0x04, 0x05])),
+                ],
+            )
+            .escape_policy(EscapePolicy::PassLiteral),
+            DecodeCase::new(
+                "abort_on_invalid_escape",
+                vec![
+                    Feed(&INVALID_ESCAPE),
+                    Decode(Error(|err| {
+                        matches!(err, SlipError::InvalidEscape { byte: 0x02, .. })
+                    })),
+                ],
+            )
+            .escape_policy(EscapePolicy::Abort),
+            DecodeCase::new(
+                "skip_oversized_frame",
+                vec![
+                    Feed(&[
+                        0xc0, 0x01, 0x02, 0x03, 0x04, 0x05, 0xc0, 0x06, 0x07, 0xc0, 0x08, 0xdb,
+                        0xdd, 0xc0,
+                    ]),
+                    Decode(Frame(&[0x06, 0x07])),
+                    Decode(Frame(&[0x08, 0xdb])),
+                    Decode(Pending),
+                ],
+            )
+            .capacity(4),
+            DecodeCase::new(
+                "skip_oversized_frame_across_reads",
+                vec![
+                    Feed(&[0xc0, 0x01, 0x02, 0x03]),
+                    Decode(Pending),
+                    Feed(&[0x04, 0x05, 0x06, 0x07]),
+                    Decode(Pending),
+                    Partial(0),
+                    Buffered(0),
+                    Feed(&[0x08, 0xc0, 0x09, 0x0a, 0xc0]),
+                    Decode(Frame(&[0x09, 0x0a])),
+                ],
+            )
+            .capacity(4),
+            DecodeCase::new(
+                "skip_frame_over_max_len",
+                vec![
+                    Feed(&[0xc0, 0x01, 0x02, 0x03, 0xc0, 0x04, 0x05, 0xc0]),
+                    Decode(Frame(&[0x04, 0x05])),
+                ],
+            )
+            .max_len(2),
+            DecodeCase::new(
+                "eof_discard",
+                vec![
+                    Feed(&TRUNCATED),
+                    DecodeEof(Frame(&[0x01, 0x02])),
+                    DecodeEof(Pending),
+                    Buffered(0),
+                ],
+            ),
+            DecodeCase::new(
+                "eof_emit",
+                vec![
+                    Feed(&TRUNCATED),
+                    DecodeEof(Frame(&[0x01, 0x02])),
+                    DecodeEof(Frame(&[0x03, 0xc0])),
+                    DecodeEof(Pending),
+                    Buffered(0),
+                ],
+            )
+            .eof_policy(EofPolicy::Emit),
+            DecodeCase::new(
+                "eof_error",
+                vec![
+                    Feed(&TRUNCATED),
+                    DecodeEof(Frame(&[0x01, 0x02])),
+                    DecodeEof(Error(|err| matches!(err, SlipError::Truncated { len: 2 }))),
+                    Buffered(0),
+                ],
+            )
+            .eof_policy(EofPolicy::Error),
+            DecodeCase::new(
+                "stats",
+                vec![
+                    Feed(&[
+                        0xc0, 0x01, 0x02, 0xc0, 0xc0, 0x03, 0x04, 0x05, 0x06, 0xc0, 0x07, 0xc0,
+                    ]),
+                    Decode(Frame(&[0x01, 0x02])),
+                    Decode(Frame(&[0x07])),
+                    Stats(SlipStats {
+                        frames_decoded: 2,
+                        bytes_in: 12,
+                        bytes_out: 3,
+                        empty_frames: 2,
+                        oversize_drops: 1,
+                        ..Default::default()
+                    }),
+                    ResetStats,
+                    Stats(SlipStats::default()),
+                ],
+            )
+            .capacity(3),
+        ]
+    }
+
+    fn check(name: &str, result: Result<Option<Vec<u8>>, SlipError>, expect: &Expect) {
+        match (expect, result) {
+            (Expect::Frame(expected), Ok(Some(frame))) => {
+                assert_eq!(*expected, &frame[..], "{}", name)
+            }
+            (Expect::Pending, Ok(None)) => {}
+            (Expect::Error(matches), Err(err)) if matches(&err) => {}
+            (_, result) => panic!("{}: unexpected {:?}", name, result),
+        }
+    }
+
+    /// Runs the shared decoder test table against a front-end
+    pub(crate) fn decode_table<F>()
+    where
+        SlipCodec<F>: FrontEnd,
+    {
+        for case in decode_cases() {
+            let builder = SlipCodec::<F>::builder()
+                .capacity(case.capacity)
+                .escape_policy(case.escape_policy)
+                .eof_policy(case.eof_policy)
+                .empty_frames(case.empty_frames);
+            let builder = match case.max_len {
+                Some(max_len) => builder.max_len(max_len),
+                None => builder,
+            };
+            let mut codec = builder.build();
+            let mut src = BytesMut::new();
+
+            for step in case.steps.iter() {
+                match step {
+                    Step::Feed(input) => src.extend_from_slice(input),
+                    Step::Decode(expect) => check(case.name, codec.decode_frame(&mut src), expect),
+                    Step::DecodeEof(expect) => {
+                        check(case.name, codec.decode_frame_eof(&mut src), expect)
+                    }
+                    Step::Buffered(len) => assert_eq!(*len, src.len(), "{}", case.name),
+                    Step::Partial(len) => {
+                        assert_eq!(*len, codec.decoder.buf.len(), "{}", case.name)
+                    }
+                    Step::Recoveries(count) => {
+                        assert_eq!(*count, codec.decoder.inner.recoveries(), "{}", case.name)
+                    }
+                    Step::Stats(stats) => {
+                        assert_eq!(stats, codec.stats(), "{}", case.name)
+                    }
+                    Step::ResetStats => codec.reset_stats(),
+                }
+            }
+        }
+    }
+
+    struct EncodeCase {
+        name: &'static str,
+        begin_with_end: bool,
+        empty_frames: bool,
+        input: &'static [u8],
+        expected: &'static [u8],
+        /// The destination is reserved exactly once for the whole frame
+        exact_capacity: bool,
+    }
+
+    const ENCODE_CASES: [EncodeCase; 8] = [
+        // default is to begin and end with END tokens
+        EncodeCase {
+            name: "empty_encode",
+            begin_with_end: true,
+            empty_frames: false,
+            input: &[],
+            expected: &[0xc0, 0xc0],
+            exact_capacity: false,
+        },
+        // override to only use END token to terminate packet
+        EncodeCase {
+            name: "empty_encode_without_leading_end",
+            begin_with_end: false,
+            empty_frames: false,
+            input: &[],
+            expected: &[0xc0],
+            exact_capacity: false,
+        },
+        // an explicit empty frame is a single END token
+        EncodeCase {
+            name: "encode_explicit_empty_frame",
+            begin_with_end: true,
+            empty_frames: true,
+            input: &[],
+            expected: &[0xc0],
+            exact_capacity: false,
+        },
+        // empty frames leave the leading END to begin_with_end
+        EncodeCase {
+            name: "encode_empty_frames_with_leading_end",
+            begin_with_end: true,
+            empty_frames: true,
+            input: &[0x01],
+            expected: &[0xc0, 0x01, 0xc0],
+            exact_capacity: false,
+        },
+        EncodeCase {
+            name: "encode_empty_frames_without_leading_end",
+            begin_with_end: false,
+            empty_frames: true,
+            input: &[0x01],
+            expected: &[0x01, 0xc0],
+            exact_capacity: false,
+        },
+        EncodeCase {
+            name: "encode_esc_esc_sequence",
+            begin_with_end: true,
+            empty_frames: false,
+            input: &[0x01, ESC, 0x03],
+            expected: &[0xc0, 0x01, ESC, ESC_ESC, 0x03, 0xc0],
+            exact_capacity: false,
+        },
+        EncodeCase {
+            name: "encode_end_esc_sequence",
+            begin_with_end: true,
+            empty_frames: false,
+            input: &[0x01, END, 0x03],
+            expected: &[0xc0, 0x01, ESC, ESC_END, 0x03, 0xc0],
+            exact_capacity: false,
+        },
+        EncodeCase {
+            name: "encode_reserves_exact_capacity",
+            begin_with_end: true,
+            empty_frames: false,
+            input: &[0x01, END, ESC, 0x03],
+            expected: &[0xc0, 0x01, ESC, ESC_END, ESC, ESC_ESC, 0x03, 0xc0],
+            exact_capacity: true,
+        },
+    ];
+
+    /// Runs the shared encoder test table against a front-end
+    pub(crate) fn encode_table<F>()
+    where
+        SlipCodec<F>: FrontEnd,
+    {
+        for case in ENCODE_CASES.iter() {
+            let mut codec = SlipCodec::<F>::builder()
+                .begin_with_end(case.begin_with_end)
+                .empty_frames(case.empty_frames)
+                .build();
+            let mut dst = BytesMut::new();
+
+            codec.encode_frame(case.input, &mut dst).unwrap();
+            assert_eq!(case.expected, &dst[..], "{}", case.name);
+            if case.exact_capacity {
+                assert_eq!(case.expected.len(), dst.capacity(), "{}", case.name);
+            }
+        }
+    }
+
+    /// Runs the shared end of stream test against a front-end's framed reader
+    pub(crate) fn stream_ends_mid_frame<F>()
+    where
+        SlipCodec<F>: FrontEnd,
+    {
+        const STREAM: [u8; 7] = [0xc0, 0x01, 0x02, 0xc0, 0x03, 0x04, 0x05];
+
+        let codec = SlipCodec::<F>::builder()
+            .eof_policy(EofPolicy::Emit)
+            .build();
+        let (frames, error) = codec.read_frames(&STREAM);
+        assert_eq!(vec![vec![0x01, 0x02], vec![0x03, 0x04, 0x05]], frames);
+        assert!(error.is_none());
+
+        let codec = SlipCodec::<F>::builder()
+            .eof_policy(EofPolicy::Error)
+            .build();
+        let (frames, error) = codec.read_frames(&STREAM);
+        assert_eq!(vec![vec![0x01, 0x02]], frames);
+        assert!(matches!(error, Some(SlipError::Truncated { len: 3 })));
+
+        let (frames, error) = SlipCodec::<F>::new().read_frames(&STREAM);
+        assert_eq!(vec![vec![0x01, 0x02]], frames);
+        assert!(error.is_none());
+    }
+
+    #[test]
+    fn build_from_config() {
+        let mut codec = SlipCodec::<BytesMut>::from_config(SlipCodecConfig {
+            begin_with_end: false,
+            ..Default::default()
+        });
+        let mut dst = BytesMut::new();
+
+        codec.encode_item(b"slip", &mut dst).unwrap();
+        assert_eq!(b"slip\xc0", &dst[..]);
+    }
+
+    #[test]
+    fn empty_frames_round_trip() {
+        const ITEMS: [&[u8]; 5] = [&[], &[0x01], &[], &[], &[0xc0, 0x02]];
+
+        let mut codec = SlipCodec::<BytesMut>::builder()
+            .begin_with_end(false)
+            .empty_frames(true)
+            .build();
+        let mut buf = BytesMut::new();
+
+        for item in ITEMS.iter() {
+            codec.encode_item(item, &mut buf).unwrap();
+        }
+        for item in ITEMS.iter() {
+            assert_eq!(Some(*item), codec.decode_item(&mut buf).unwrap().as_deref());
+        }
+        assert_eq!(None, codec.decode_item(&mut buf).unwrap());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn builder_config() {
+        let builder = SlipCodecBuilder::<()>::new()
+            .begin_with_end(false)
+            .max_len(256)
+            .eof_policy(EofPolicy::Emit);
+        let config = SlipCodecConfig {
+            begin_with_end: false,
+            max_len: Some(256),
+            eof_policy: EofPolicy::Emit,
+            ..Default::default()
+        };
+
+        assert_eq!(config, builder.clone().config);
+        assert_eq!(
+            SlipCodecConfig::default(),
+            builder.config(Default::default()).config
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_config() {
+        let config: SlipCodecConfig = toml::from_str(
+            r#"
+            begin_with_end = false
+            max_len = 296
+            escape_policy = "pass_literal"
+            eof_policy = "emit"
+            empty_frames = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            SlipCodecConfig {
+                begin_with_end: false,
+                capacity: MAX_PACKET_SIZE,
+                max_len: Some(296),
+                escape_policy: EscapePolicy::PassLiteral,
+                eof_policy: EofPolicy::Emit,
+                empty_frames: true,
+            },
+            config
+        );
+        assert_eq!(SlipCodecConfig::default(), toml::from_str("").unwrap());
+        assert!(toml::from_str::<SlipCodecConfig>("mtu = 296").is_err());
+        assert!(toml::from_str::<SlipCodecConfig>("flush_policy = \"per_frame\"").is_err());
+    }
+}
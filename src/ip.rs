@@ -0,0 +1,335 @@
+//! IPv4 and IPv6 packet validation
+//!
+//! SLIP links usually carry IP packets, one per frame. [`validate`] checks
+//! that a decoded frame holds exactly one well formed IPv4 or IPv6 packet
+//! and returns a view of its header, so that garbage from a noisy line can
+//! be rejected before it reaches the network stack, e.g. through a TUN
+//! interface. [`SlipEncoder::encode_ip`] applies the same checks before
+//! encoding.
+//!
+//! [`SlipEncoder::encode_ip`]: crate::SlipEncoder::encode_ip
+
+use crate::wire::{header_checksum, read_u16_be};
+use core::net::{Ipv4Addr, Ipv6Addr};
+
+const IPV4_MIN_HEADER_LEN: usize = 20;
+const IPV6_HEADER_LEN: usize = 40;
+
+/// IP validation error type
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IpError {
+    /// The `len` byte frame is too short to hold an IP header
+    Truncated { len: usize },
+    /// The version nibble is neither 4 nor 6
+    Version(u8),
+    /// The IPv4 internet header length of `ihl` 32-bit words is invalid
+    HeaderLength { ihl: u8 },
+    /// The packet length given in the header does not match the frame
+    TotalLength { declared: usize, frame: usize },
+    /// The IPv4 header checksum `received` does not match the one `computed`
+    Checksum { received: u16, computed: u16 },
+}
+
+impl core::fmt::Display for IpError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            IpError::Truncated { len } => write!(f, "{} byte frame is too short", len),
+            IpError::Version(version) => write!(f, "unknown IP version {}", version),
+            IpError::HeaderLength { ihl } => write!(f, "invalid IPv4 header length {}", ihl),
+            IpError::TotalLength { declared, frame } => write!(
+                f,
+                "packet length {} does not match the {} byte frame",
+                declared, frame
+            ),
+            IpError::Checksum { received, computed } => write!(
+                f,
+                "header checksum 0x{:04X} does not match 0x{:04X}",
+                received, computed
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IpError {}
+
+#[cfg(feature = "std")]
+impl From<IpError> for std::io::Error {
+    fn from(err: IpError) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, err)
+    }
+}
+
+/// View of a validated IP packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpPacket<'a> {
+    /// An IPv4 packet
+    V4(Ipv4Packet<'a>),
+    /// An IPv6 packet
+    V6(Ipv6Packet<'a>),
+}
+
+impl<'a> IpPacket<'a> {
+    /// Returns the payload following the IP header
+    pub fn payload(&self) -> &'a [u8] {
+        match self {
+            IpPacket::V4(packet) => packet.payload(),
+            IpPacket::V6(packet) => packet.payload(),
+        }
+    }
+}
+
+/// View of a validated IPv4 packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Packet<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Ipv4Packet<'a> {
+    /// Returns the header length in bytes, including options
+    pub fn header_len(&self) -> usize {
+        usize::from(self.buf[0] & 0x0f) * 4
+    }
+
+    /// Returns the length of the whole packet in bytes
+    pub fn total_len(&self) -> usize {
+        usize::from(read_u16_be(self.buf, 2))
+    }
+
+    /// Returns the time to live
+    pub fn ttl(&self) -> u8 {
+        self.buf[8]
+    }
+
+    /// Returns the protocol number of the payload, e.g. 6 for TCP
+    pub fn protocol(&self) -> u8 {
+        self.buf[9]
+    }
+
+    /// Returns the source address
+    pub fn source(&self) -> Ipv4Addr {
+        Ipv4Addr::new(self.buf[12], self.buf[13], self.buf[14], self.buf[15])
+    }
+
+    /// Returns the destination address
+    pub fn destination(&self) -> Ipv4Addr {
+        Ipv4Addr::new(self.buf[16], self.buf[17], self.buf[18], self.buf[19])
+    }
+
+    /// Returns the payload following the header and its options
+    pub fn payload(&self) -> &'a [u8] {
+        &self.buf[self.header_len()..]
+    }
+}
+
+/// View of a validated IPv6 packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv6Packet<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Ipv6Packet<'a> {
+    /// Returns the length of the payload, including extension headers
+    pub fn payload_len(&self) -> usize {
+        usize::from(read_u16_be(self.buf, 4))
+    }
+
+    /// Returns the type of the header following the fixed header
+    pub fn next_header(&self) -> u8 {
+        self.buf[6]
+    }
+
+    /// Returns the hop limit
+    pub fn hop_limit(&self) -> u8 {
+        self.buf[7]
+    }
+
+    /// Returns the source address
+    pub fn source(&self) -> Ipv6Addr {
+        Ipv6Addr::from(read_addr(self.buf, 8))
+    }
+
+    /// Returns the destination address
+    pub fn destination(&self) -> Ipv6Addr {
+        Ipv6Addr::from(read_addr(self.buf, 24))
+    }
+
+    /// Returns the payload following the fixed header
+    pub fn payload(&self) -> &'a [u8] {
+        &self.buf[IPV6_HEADER_LEN..]
+    }
+}
+
+/// Checks that `frame` holds exactly one IPv4 or IPv6 packet.
+///
+/// The version, the header length, the packet length against the frame
+/// length and, for IPv4, the header checksum are verified.
+pub fn validate(frame: &[u8]) -> Result<IpPacket<'_>, IpError> {
+    let truncated = IpError::Truncated { len: frame.len() };
+    let version = match frame.first() {
+        Some(value) => value >> 4,
+        None => return Err(truncated),
+    };
+
+    match version {
+        4 => {
+            if frame.len() < IPV4_MIN_HEADER_LEN {
+                return Err(truncated);
+            }
+
+            let packet = Ipv4Packet { buf: frame };
+            let header_len = packet.header_len();
+
+            if header_len < IPV4_MIN_HEADER_LEN {
+                return Err(IpError::HeaderLength {
+                    ihl: frame[0] & 0x0f,
+                });
+            }
+
+            if packet.total_len() != frame.len() || header_len > frame.len() {
+                return Err(IpError::TotalLength {
+                    declared: packet.total_len(),
+                    frame: frame.len(),
+                });
+            }
+
+            let header = &frame[..header_len];
+
+            if header_checksum(header) != 0 {
+                let mut zeroed = [0u8; 60];
+
+                zeroed[..header_len].copy_from_slice(header);
+                zeroed[10..12].fill(0u8);
+
+                return Err(IpError::Checksum {
+                    received: read_u16_be(header, 10),
+                    computed: header_checksum(&zeroed[..header_len]),
+                });
+            }
+
+            Ok(IpPacket::V4(packet))
+        }
+        6 => {
+            if frame.len() < IPV6_HEADER_LEN {
+                return Err(truncated);
+            }
+
+            let packet = Ipv6Packet { buf: frame };
+
+            if IPV6_HEADER_LEN + packet.payload_len() != frame.len() {
+                return Err(IpError::TotalLength {
+                    declared: IPV6_HEADER_LEN + packet.payload_len(),
+                    frame: frame.len(),
+                });
+            }
+
+            Ok(IpPacket::V6(packet))
+        }
+        version => Err(IpError::Version(version)),
+    }
+}
+
+fn read_addr(buf: &[u8], offset: usize) -> [u8; 16] {
+    let mut addr = [0u8; 16];
+
+    addr.copy_from_slice(&buf[offset..offset + 16]);
+    addr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// IPv4 UDP header from 192.168.0.1 to 192.168.0.199
+    const IPV4_HEADER: [u8; 20] = [
+        0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0xb8, 0x61, 0xc0, 0xa8, 0x00,
+        0x01, 0xc0, 0xa8, 0x00, 0xc7,
+    ];
+
+    /// IPv6 UDP header from ::1 to ::2 with an 8 byte payload
+    const IPV6_HEADER: [u8; 40] = [
+        0x60, 0x00, 0x00, 0x00, 0x00, 0x08, 0x11, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+    ];
+
+    fn ipv4_packet() -> [u8; 0x73] {
+        let mut packet = [0x5au8; 0x73];
+        packet[..20].copy_from_slice(&IPV4_HEADER);
+        packet
+    }
+
+    #[test]
+    fn ipv4() {
+        let packet = ipv4_packet();
+        let header = match validate(&packet).unwrap() {
+            IpPacket::V4(header) => header,
+            _ => panic!("not an IPv4 packet"),
+        };
+        assert_eq!(20, header.header_len());
+        assert_eq!(0x73, header.total_len());
+        assert_eq!(64, header.ttl());
+        assert_eq!(17, header.protocol());
+        assert_eq!(Ipv4Addr::new(192, 168, 0, 1), header.source());
+        assert_eq!(Ipv4Addr::new(192, 168, 0, 199), header.destination());
+        assert_eq!(&packet[20..], header.payload());
+    }
+
+    #[test]
+    fn ipv4_errors() {
+        let mut packet = ipv4_packet();
+        assert_eq!(
+            Err(IpError::TotalLength {
+                declared: 0x73,
+                frame: 0x72
+            }),
+            validate(&packet[..0x72])
+        );
+        assert_eq!(Err(IpError::Truncated { len: 19 }), validate(&packet[..19]));
+
+        packet[8] = 63;
+        assert_eq!(
+            Err(IpError::Checksum {
+                received: 0xb861,
+                computed: 0xb961
+            }),
+            validate(&packet)
+        );
+
+        packet[0] = 0x44;
+        assert_eq!(Err(IpError::HeaderLength { ihl: 4 }), validate(&packet));
+    }
+
+    #[test]
+    fn ipv6() {
+        let mut packet = IPV6_HEADER.to_vec();
+        packet.extend_from_slice(&[0xa5; 8]);
+
+        let header = match validate(&packet).unwrap() {
+            IpPacket::V6(header) => header,
+            _ => panic!("not an IPv6 packet"),
+        };
+        assert_eq!(8, header.payload_len());
+        assert_eq!(17, header.next_header());
+        assert_eq!(64, header.hop_limit());
+        assert_eq!(Ipv6Addr::LOCALHOST, header.source());
+        assert_eq!(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 2), header.destination());
+        assert_eq!(&[0xa5; 8], header.payload());
+
+        packet.push(0x00);
+        assert_eq!(
+            Err(IpError::TotalLength {
+                declared: 48,
+                frame: 49
+            }),
+            validate(&packet)
+        );
+    }
+
+    #[test]
+    fn not_ip() {
+        assert_eq!(Err(IpError::Truncated { len: 0 }), validate(&[]));
+        assert_eq!(Err(IpError::Version(0)), validate(&[0x01, 0x02, 0x03]));
+    }
+}
@@ -2,6 +2,19 @@
 //! 
 //! [`SlipEncoder`] and [`SlipDecoder`] facilitate encoder and decoding of SLIP
 //! data streams with `std::io::Read` and `std::io::Write` interfaces.
+//! [`SlipReader`] wraps any `std::io::Read` source and yields decoded frames,
+//! while [`SlipWriter`] streams frames of any size into a `std::io::Write`
+//! sink.
+//! 
+//! The `std` feature is enabled by default. Without it, the crate is
+//! `#![no_std]` and does not require an allocator: [`SlipEncoder::encode_slice`]
+//! and [`SlipDecoder::decode_slice`] operate directly on caller provided
+//! slices and report the number of bytes consumed and produced.
+//! [`SlipEncoder::encode_to_slice`] encodes a whole frame into a fixed buffer
+//! that can be sized exactly with [`SlipEncoder::encoded_len`].
+//! 
+//! [`Frames`] iterates over every complete frame in a buffer, decoding each
+//! one in place and yielding it as a sub-slice of the buffer.
 //! 
 //! Enabling the `tokio-codec` feature makes a codec available for use with
 //! the tokio runtime (see [`tokio::SlipCodec`]). If a different asynchronous
@@ -10,14 +23,65 @@
 //! 
 //! [`SlipEncoder`]: crate::SlipEncoder
 //! [`SlipDecoder`]: crate::SlipDecoder
+//! [`SlipReader`]: crate::SlipReader
+//! [`SlipWriter`]: crate::SlipWriter
 //! [`tokio::SlipCodec`]: crate::tokio::SlipCodec
 //! [`aio::SlipCodec`]: crate::aio::SlipCodec
+//! [`SlipEncoder::encode_slice`]: crate::SlipEncoder::encode_slice
+//! [`SlipEncoder::encode_to_slice`]: crate::SlipEncoder::encode_to_slice
+//! [`SlipEncoder::encoded_len`]: crate::SlipEncoder::encoded_len
+//! [`SlipDecoder::decode_slice`]: crate::SlipDecoder::decode_slice
+//! [`Frames`]: crate::Frames
+
+#![cfg_attr(not(feature = "std"), no_std)]
 
 mod encoder;
-pub use encoder::SlipEncoder;
+pub use encoder::{BufferTooSmall, Encoded, FlushPolicy, SlipEncoder, SlipEncoderBuilder};
 
 mod decoder;
-pub use decoder::{SlipDecoder, SlipError, SlipResult};
+pub use decoder::{
+    Decoded, EofPolicy, EscapePolicy, SlipDecoder, SlipDecoderBuilder, SlipError, SlipResult,
+};
+
+mod frames;
+pub use frames::Frames;
+
+mod stats;
+pub use stats::SlipStats;
+
+#[cfg(feature = "checksum")]
+pub mod checksum;
+
+#[cfg(feature = "cslip")]
+pub mod cslip;
+
+#[cfg(feature = "esp")]
+pub mod esp;
+
+#[cfg(feature = "ip")]
+pub mod ip;
+
+#[cfg(feature = "nrf_dfu")]
+pub mod nrf_dfu;
+
+#[cfg(any(feature = "cslip", feature = "esp", feature = "ip", feature = "nrf_dfu"))]
+mod wire;
+
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+pub use reader::SlipReader;
+
+#[cfg(feature = "std")]
+mod writer;
+#[cfg(feature = "std")]
+pub use writer::{SlipWriter, SlipWriterBuilder};
+
+#[cfg(any(feature = "async-codec", feature = "tokio-codec"))]
+mod framing;
+
+#[cfg(all(test, feature = "std"))]
+mod test_util;
 
 #[cfg(feature = "async-codec")]
 pub mod aio;
@@ -38,5 +102,4 @@ const ESC_END: u8 = 0xDC;
 const ESC_ESC: u8 = 0xDD;
 
 /// Recommended maximum SLIP packet size per RFC 1055
-#[allow(dead_code)]
-const MAX_PACKET_SIZE: usize = 1006;
+pub const MAX_PACKET_SIZE: usize = 1006;
@@ -0,0 +1,781 @@
+//! Nordic nRF5 SDK secure bootloader DFU over serial
+//!
+//! The serial DFU transport of the nRF5 SDK bootloader carries one request
+//! or response per SLIP frame. A request is an opcode followed by its
+//! parameters; a response starts with `0x60`, the opcode of the request and
+//! a result code. All multi-byte fields are little endian.
+//!
+//! An upload transfers the init packet as a command object and the firmware
+//! image as a sequence of data objects. Each object is created, written in
+//! chunks that fit the target's MTU, verified with a CRC-32 over all data of
+//! its type and executed. [`DfuClient`] drives this sequence over a
+//! [`tokio::SlipCodec`](crate::tokio::SlipCodec) framed I/O object.
+
+use crate::checksum::Crc32;
+use crate::tokio::SlipCodec;
+use crate::wire::read_u32_le;
+use crate::SlipError;
+use ::tokio::io::{AsyncRead, AsyncWrite};
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::future::poll_fn;
+use std::pin::Pin;
+use tokio_util::codec::Framed;
+
+/// Request opcodes
+pub mod opcode {
+    pub const OBJECT_CREATE: u8 = 0x01;
+    pub const RECEIPT_NOTIF_SET: u8 = 0x02;
+    pub const CRC_GET: u8 = 0x03;
+    pub const OBJECT_EXECUTE: u8 = 0x04;
+    pub const OBJECT_SELECT: u8 = 0x06;
+    pub const MTU_GET: u8 = 0x07;
+    pub const OBJECT_WRITE: u8 = 0x08;
+    pub const PING: u8 = 0x09;
+    pub const RESPONSE: u8 = 0x60;
+}
+
+/// Result code of a successful request
+pub const RESULT_SUCCESS: u8 = 0x01;
+
+/// Result code followed by an extended error code
+pub const RESULT_EXT_ERROR: u8 = 0x0B;
+
+/// Kind of DFU object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectType {
+    /// The init packet
+    Command = 0x01,
+    /// The firmware image
+    Data = 0x02,
+}
+
+impl ObjectType {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0x01 => Some(ObjectType::Command),
+            0x02 => Some(ObjectType::Data),
+            _ => None,
+        }
+    }
+}
+
+/// DFU error type
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DfuError {
+    /// The underlying codec or I/O object failed
+    Slip(SlipError),
+    /// The `len` byte message could not be parsed
+    Malformed { len: usize },
+    /// The message uses an unknown opcode or object type
+    Unsupported(u8),
+    /// The response belongs to a different request
+    UnexpectedResponse { expected: u8, received: u8 },
+    /// The target rejected the request `opcode` with the given result code
+    /// and, for [`RESULT_EXT_ERROR`], extended error code
+    Target {
+        opcode: u8,
+        result: u8,
+        extended: Option<u8>,
+    },
+    /// The target holds `received` bytes with CRC `received_crc` instead of
+    /// `expected` bytes with CRC `expected_crc`
+    Verification {
+        expected: u32,
+        expected_crc: u32,
+        received: u32,
+        received_crc: u32,
+    },
+    /// The target closed the connection
+    Closed,
+}
+
+impl core::fmt::Display for DfuError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DfuError::Slip(err) => write!(f, "{}", err),
+            DfuError::Malformed { len } => write!(f, "malformed {} byte message", len),
+            DfuError::Unsupported(value) => write!(f, "unsupported value 0x{:02X}", value),
+            DfuError::UnexpectedResponse { expected, received } => write!(
+                f,
+                "response to opcode 0x{:02X} while waiting for 0x{:02X}",
+                received, expected
+            ),
+            DfuError::Target {
+                opcode,
+                result,
+                extended: Some(extended),
+            } => write!(
+                f,
+                "opcode 0x{:02X} failed with result 0x{:02X}, extended error 0x{:02X}",
+                opcode, result, extended
+            ),
+            DfuError::Target { opcode, result, .. } => write!(
+                f,
+                "opcode 0x{:02X} failed with result 0x{:02X}",
+                opcode, result
+            ),
+            DfuError::Verification {
+                expected,
+                expected_crc,
+                received,
+                received_crc,
+            } => write!(
+                f,
+                "target holds {} bytes with CRC 0x{:08X}, expected {} bytes with CRC 0x{:08X}",
+                received, received_crc, expected, expected_crc
+            ),
+            DfuError::Closed => write!(f, "connection closed by the target"),
+        }
+    }
+}
+
+impl std::error::Error for DfuError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DfuError::Slip(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SlipError> for DfuError {
+    fn from(err: SlipError) -> Self {
+        DfuError::Slip(err)
+    }
+}
+
+/// Request sent to the bootloader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request<'a> {
+    /// Create an object of `size` bytes
+    Create { object_type: ObjectType, size: u32 },
+    /// Request a CRC notification every `prn` writes, or never if 0
+    SetReceiptNotification { prn: u16 },
+    /// Read the offset and CRC of the data received so far
+    CrcGet,
+    /// Execute the current object
+    Execute,
+    /// Select the last object of the given type
+    Select { object_type: ObjectType },
+    /// Read the maximum size of an encoded SLIP frame
+    MtuGet,
+    /// Write data to the current object; there is no response
+    Write { data: &'a [u8] },
+    /// Check that the target is responsive
+    Ping { id: u8 },
+}
+
+impl<'a> Request<'a> {
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Request::Create { .. } => opcode::OBJECT_CREATE,
+            Request::SetReceiptNotification { .. } => opcode::RECEIPT_NOTIF_SET,
+            Request::CrcGet => opcode::CRC_GET,
+            Request::Execute => opcode::OBJECT_EXECUTE,
+            Request::Select { .. } => opcode::OBJECT_SELECT,
+            Request::MtuGet => opcode::MTU_GET,
+            Request::Write { .. } => opcode::OBJECT_WRITE,
+            Request::Ping { .. } => opcode::PING,
+        }
+    }
+
+    /// Returns the message carried in a SLIP frame
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut message = vec![self.opcode()];
+
+        match self {
+            Request::Create { object_type, size } => {
+                message.push(*object_type as u8);
+                message.extend_from_slice(&size.to_le_bytes());
+            }
+            Request::SetReceiptNotification { prn } => {
+                message.extend_from_slice(&prn.to_le_bytes());
+            }
+            Request::Select { object_type } => message.push(*object_type as u8),
+            Request::Write { data } => message.extend_from_slice(data),
+            Request::Ping { id } => message.push(*id),
+            Request::CrcGet | Request::Execute | Request::MtuGet => {}
+        }
+
+        message
+    }
+
+    /// Parses a request message, e.g. to simulate a target
+    pub fn parse(message: &'a [u8]) -> Result<Self, DfuError> {
+        let malformed = || DfuError::Malformed { len: message.len() };
+        let (opcode, params) = message.split_first().ok_or_else(malformed)?;
+        let object_type = || {
+            let value = *params.first().ok_or_else(malformed)?;
+
+            ObjectType::from_u8(value).ok_or(DfuError::Unsupported(value))
+        };
+        let exact = |len: usize| {
+            if params.len() == len {
+                Ok(())
+            } else {
+                Err(malformed())
+            }
+        };
+
+        let request = match *opcode {
+            opcode::OBJECT_CREATE => {
+                exact(5)?;
+                Request::Create {
+                    object_type: object_type()?,
+                    size: read_u32_le(params, 1),
+                }
+            }
+            opcode::RECEIPT_NOTIF_SET => {
+                exact(2)?;
+                Request::SetReceiptNotification {
+                    prn: u16::from_le_bytes([params[0], params[1]]),
+                }
+            }
+            opcode::CRC_GET => exact(0).map(|_| Request::CrcGet)?,
+            opcode::OBJECT_EXECUTE => exact(0).map(|_| Request::Execute)?,
+            opcode::OBJECT_SELECT => {
+                exact(1)?;
+                Request::Select {
+                    object_type: object_type()?,
+                }
+            }
+            opcode::MTU_GET => exact(0).map(|_| Request::MtuGet)?,
+            opcode::OBJECT_WRITE => Request::Write { data: params },
+            opcode::PING => {
+                exact(1)?;
+                Request::Ping { id: params[0] }
+            }
+            opcode => return Err(DfuError::Unsupported(opcode)),
+        };
+
+        Ok(request)
+    }
+}
+
+/// Successful response from the bootloader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// The object was created
+    Created,
+    /// The receipt notification interval was set
+    ReceiptNotificationSet,
+    /// `offset` bytes with CRC-32 `crc` have been received
+    Crc { offset: u32, crc: u32 },
+    /// The object was executed
+    Executed,
+    /// Objects hold at most `max_size` bytes; `offset` bytes with CRC-32
+    /// `crc` have been received
+    Selected {
+        max_size: u32,
+        offset: u32,
+        crc: u32,
+    },
+    /// Encoded frames are limited to `mtu` bytes
+    Mtu { mtu: u16 },
+    /// Reply to [`Request::Ping`]
+    Pong { id: u8 },
+}
+
+impl Response {
+    /// Returns the opcode of the request answered
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Response::Created => opcode::OBJECT_CREATE,
+            Response::ReceiptNotificationSet => opcode::RECEIPT_NOTIF_SET,
+            Response::Crc { .. } => opcode::CRC_GET,
+            Response::Executed => opcode::OBJECT_EXECUTE,
+            Response::Selected { .. } => opcode::OBJECT_SELECT,
+            Response::Mtu { .. } => opcode::MTU_GET,
+            Response::Pong { .. } => opcode::PING,
+        }
+    }
+
+    /// Returns the message carried in a SLIP frame
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut message = vec![opcode::RESPONSE, self.opcode(), RESULT_SUCCESS];
+
+        match self {
+            Response::Crc { offset, crc } => {
+                message.extend_from_slice(&offset.to_le_bytes());
+                message.extend_from_slice(&crc.to_le_bytes());
+            }
+            Response::Selected {
+                max_size,
+                offset,
+                crc,
+            } => {
+                message.extend_from_slice(&max_size.to_le_bytes());
+                message.extend_from_slice(&offset.to_le_bytes());
+                message.extend_from_slice(&crc.to_le_bytes());
+            }
+            Response::Mtu { mtu } => message.extend_from_slice(&mtu.to_le_bytes()),
+            Response::Pong { id } => message.push(*id),
+            _ => {}
+        }
+
+        message
+    }
+
+    /// Parses a response message, reporting a failed request as
+    /// [`DfuError::Target`]
+    pub fn parse(message: &[u8]) -> Result<Self, DfuError> {
+        let malformed = DfuError::Malformed { len: message.len() };
+
+        if message.len() < 3 || message[0] != opcode::RESPONSE {
+            return Err(malformed);
+        }
+
+        let (opcode, result, params) = (message[1], message[2], &message[3..]);
+
+        if result != RESULT_SUCCESS {
+            return Err(DfuError::Target {
+                opcode,
+                result,
+                extended: params
+                    .first()
+                    .copied()
+                    .filter(|_| result == RESULT_EXT_ERROR),
+            });
+        }
+
+        let expected = match opcode {
+            opcode::CRC_GET => 8,
+            opcode::OBJECT_SELECT => 12,
+            opcode::MTU_GET => 2,
+            opcode::PING => 1,
+            _ => 0,
+        };
+
+        if params.len() != expected {
+            return Err(malformed);
+        }
+
+        let response = match opcode {
+            opcode::OBJECT_CREATE => Response::Created,
+            opcode::RECEIPT_NOTIF_SET => Response::ReceiptNotificationSet,
+            opcode::CRC_GET => Response::Crc {
+                offset: read_u32_le(params, 0),
+                crc: read_u32_le(params, 4),
+            },
+            opcode::OBJECT_EXECUTE => Response::Executed,
+            opcode::OBJECT_SELECT => Response::Selected {
+                max_size: read_u32_le(params, 0),
+                offset: read_u32_le(params, 4),
+                crc: read_u32_le(params, 8),
+            },
+            opcode::MTU_GET => Response::Mtu {
+                mtu: u16::from_le_bytes([params[0], params[1]]),
+            },
+            opcode::PING => Response::Pong { id: params[0] },
+            opcode => return Err(DfuError::Unsupported(opcode)),
+        };
+
+        Ok(response)
+    }
+}
+
+/// Progress of an upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DfuState {
+    /// No upload has started
+    Idle,
+    /// The connection parameters are being negotiated
+    Setup,
+    /// `offset` bytes of the init packet have been executed
+    InitPacket { offset: usize },
+    /// `offset` bytes of the firmware image have been executed
+    Firmware { offset: usize },
+    /// The whole firmware image has been executed
+    Complete,
+}
+
+/// Drives a DFU upload over a SLIP framed I/O object
+#[derive(Debug)]
+pub struct DfuClient<T> {
+    framed: Framed<T, SlipCodec>,
+    mtu: usize,
+    state: DfuState,
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> DfuClient<T> {
+    pub fn new(framed: Framed<T, SlipCodec>) -> Self {
+        Self {
+            framed,
+            mtu: 0usize,
+            state: DfuState::Idle,
+        }
+    }
+
+    /// Returns the progress of the current or last upload
+    pub fn state(&self) -> DfuState {
+        self.state
+    }
+
+    pub fn into_inner(self) -> Framed<T, SlipCodec> {
+        self.framed
+    }
+
+    /// Uploads the init packet and the firmware image, then lets the
+    /// bootloader activate the new firmware
+    pub async fn upload(&mut self, init_packet: &[u8], firmware: &[u8]) -> Result<(), DfuError> {
+        self.state = DfuState::Setup;
+        self.request(&Request::SetReceiptNotification { prn: 0 })
+            .await?;
+
+        match self.request(&Request::MtuGet).await? {
+            Response::Mtu { mtu } => self.mtu = usize::from(mtu),
+            response => return Err(unexpected(opcode::MTU_GET, &response)),
+        }
+
+        self.state = DfuState::InitPacket { offset: 0 };
+        self.transfer(ObjectType::Command, init_packet).await?;
+        self.state = DfuState::Firmware { offset: 0 };
+        self.transfer(ObjectType::Data, firmware).await?;
+        self.state = DfuState::Complete;
+
+        Ok(())
+    }
+
+    /// Sends a request and waits for its response
+    pub async fn request(&mut self, request: &Request<'_>) -> Result<Response, DfuError> {
+        self.send(request).await?;
+
+        let message = poll_fn(|cx| Pin::new(&mut self.framed).poll_next(cx))
+            .await
+            .ok_or(DfuError::Closed)??;
+        let response = Response::parse(&message)?;
+
+        if response.opcode() != request.opcode() {
+            return Err(unexpected(request.opcode(), &response));
+        }
+
+        Ok(response)
+    }
+
+    async fn send(&mut self, request: &Request<'_>) -> Result<(), DfuError> {
+        poll_fn(|cx| Sink::<Bytes>::poll_ready(Pin::new(&mut self.framed), cx)).await?;
+        Pin::new(&mut self.framed).start_send(Bytes::from(request.to_bytes()))?;
+        poll_fn(|cx| Sink::<Bytes>::poll_flush(Pin::new(&mut self.framed), cx)).await?;
+
+        Ok(())
+    }
+
+    /// Transfers `data` in as many objects of the given type as needed
+    async fn transfer(&mut self, object_type: ObjectType, data: &[u8]) -> Result<(), DfuError> {
+        let max_size = match self.request(&Request::Select { object_type }).await? {
+            Response::Selected { max_size, .. } => max_size as usize,
+            response => return Err(unexpected(opcode::OBJECT_SELECT, &response)),
+        };
+        // every byte of a write may need escaping, plus the opcode and END
+        let chunk_len = (self.mtu.saturating_sub(1) / 2).saturating_sub(1).max(1);
+        let mut digest = Crc32.digest();
+        let mut offset = 0usize;
+
+        for object in data.chunks(max_size.max(1)) {
+            self.request(&Request::Create {
+                object_type,
+                size: object.len() as u32,
+            })
+            .await?;
+
+            for chunk in object.chunks(chunk_len) {
+                self.send(&Request::Write { data: chunk }).await?;
+            }
+
+            digest.update(object);
+            offset += object.len();
+            self.verify(offset as u32, digest.clone().finalize())
+                .await?;
+            self.request(&Request::Execute).await?;
+            self.state = match object_type {
+                ObjectType::Command => DfuState::InitPacket { offset },
+                ObjectType::Data => DfuState::Firmware { offset },
+            };
+        }
+
+        Ok(())
+    }
+
+    async fn verify(&mut self, expected: u32, expected_crc: u32) -> Result<(), DfuError> {
+        match self.request(&Request::CrcGet).await? {
+            Response::Crc { offset, crc } if offset == expected && crc == expected_crc => Ok(()),
+            Response::Crc { offset, crc } => Err(DfuError::Verification {
+                expected,
+                expected_crc,
+                received: offset,
+                received_crc: crc,
+            }),
+            response => Err(unexpected(opcode::CRC_GET, &response)),
+        }
+    }
+}
+
+fn unexpected(expected: u8, response: &Response) -> DfuError {
+    DfuError::UnexpectedResponse {
+        expected,
+        received: response.opcode(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::Checksum;
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::DuplexStream;
+
+    /// Bootloader simulation that keeps every executed object
+    struct Target {
+        framed: Framed<DuplexStream, SlipCodec>,
+        mtu: u16,
+        max_size: [u32; 2],
+        executed: [Vec<u8>; 2],
+        current: Option<(ObjectType, u32, Vec<u8>)>,
+        max_create: u32,
+        corrupt_write: Option<usize>,
+        writes: usize,
+    }
+
+    impl Target {
+        fn new(io: DuplexStream) -> Self {
+            Self {
+                framed: Framed::new(io, SlipCodec::new()),
+                mtu: 64,
+                max_size: [256, 1024],
+                executed: [Vec::new(), Vec::new()],
+                current: None,
+                max_create: u32::MAX,
+                corrupt_write: None,
+                writes: 0usize,
+            }
+        }
+
+        /// Returns all data of the given type, including the current object
+        fn received(&self, object_type: ObjectType) -> Vec<u8> {
+            let mut data = self.executed[object_type as usize - 1].clone();
+
+            if let Some((current, _, buf)) = &self.current {
+                if *current == object_type {
+                    data.extend_from_slice(buf);
+                }
+            }
+
+            data
+        }
+
+        fn handle(&mut self, request: Request<'_>) -> Option<Result<Response, u8>> {
+            let response = match request {
+                Request::SetReceiptNotification { .. } => Response::ReceiptNotificationSet,
+                Request::MtuGet => Response::Mtu { mtu: self.mtu },
+                Request::Ping { id } => Response::Pong { id },
+                Request::Select { object_type } => {
+                    let data = self.received(object_type);
+
+                    Response::Selected {
+                        max_size: self.max_size[object_type as usize - 1],
+                        offset: data.len() as u32,
+                        crc: Crc32.checksum(&data),
+                    }
+                }
+                Request::Create { object_type, size } => {
+                    if size > self.max_size[object_type as usize - 1].min(self.max_create) {
+                        return Some(Err(0x04));
+                    }
+
+                    self.current = Some((object_type, size, Vec::new()));
+                    Response::Created
+                }
+                Request::Write { data } => {
+                    let (_, size, buf) = self.current.as_mut()?;
+
+                    assert!(2 * (data.len() + 1) < usize::from(self.mtu));
+                    assert!(buf.len() + data.len() <= *size as usize);
+                    buf.extend_from_slice(data);
+
+                    if self.corrupt_write == Some(self.writes) {
+                        *buf.last_mut().unwrap() ^= 0xff;
+                    }
+
+                    self.writes += 1;
+                    return None;
+                }
+                Request::CrcGet => {
+                    let (object_type, _, _) = self.current.as_ref().unwrap();
+                    let data = self.received(*object_type);
+
+                    Response::Crc {
+                        offset: data.len() as u32,
+                        crc: Crc32.checksum(&data),
+                    }
+                }
+                Request::Execute => {
+                    let (object_type, size, buf) = self.current.take().unwrap();
+
+                    if buf.len() != size as usize {
+                        return Some(Err(0x08));
+                    }
+
+                    self.executed[object_type as usize - 1].extend_from_slice(&buf);
+                    Response::Executed
+                }
+            };
+
+            Some(Ok(response))
+        }
+
+        async fn run(mut self) -> Self {
+            while let Some(Ok(message)) = self.framed.next().await {
+                let request = Request::parse(&message).unwrap();
+                let opcode = request.opcode();
+                let reply = match self.handle(request) {
+                    Some(Ok(response)) => response.to_bytes(),
+                    Some(Err(result)) => vec![opcode::RESPONSE, opcode, result],
+                    None => continue,
+                };
+
+                self.framed.send(Bytes::from(reply)).await.unwrap();
+            }
+
+            self
+        }
+    }
+
+    fn image(len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|index| (index * 7 % 251) as u8 ^ 0xc0)
+            .collect()
+    }
+
+    #[test]
+    fn request_bytes() {
+        let requests: [(Request<'_>, &[u8]); 6] = [
+            (
+                Request::Create {
+                    object_type: ObjectType::Data,
+                    size: 0x1000,
+                },
+                &[0x01, 0x02, 0x00, 0x10, 0x00, 0x00],
+            ),
+            (
+                Request::SetReceiptNotification { prn: 0x0102 },
+                &[0x02, 0x02, 0x01],
+            ),
+            (Request::CrcGet, &[0x03]),
+            (
+                Request::Select {
+                    object_type: ObjectType::Command,
+                },
+                &[0x06, 0x01],
+            ),
+            (
+                Request::Write {
+                    data: &[0xc0, 0xdb],
+                },
+                &[0x08, 0xc0, 0xdb],
+            ),
+            (Request::Ping { id: 0x2a }, &[0x09, 0x2a]),
+        ];
+
+        for (request, bytes) in requests.iter() {
+            assert_eq!(*bytes, request.to_bytes().as_slice());
+            assert_eq!(*request, Request::parse(bytes).unwrap());
+        }
+    }
+
+    #[test]
+    fn response_bytes() {
+        const SELECTED: [u8; 15] = [
+            0x60, 0x06, 0x01, 0x00, 0x10, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x78, 0x56, 0x34,
+            0x12,
+        ];
+
+        let selected = Response::Selected {
+            max_size: 0x1000,
+            offset: 0x80,
+            crc: 0x1234_5678,
+        };
+        assert_eq!(&SELECTED[..], selected.to_bytes().as_slice());
+        assert_eq!(selected, Response::parse(&SELECTED).unwrap());
+        assert_eq!(
+            Response::Mtu { mtu: 0x0407 },
+            Response::parse(&[0x60, 0x07, 0x01, 0x07, 0x04]).unwrap()
+        );
+        assert!(matches!(
+            Response::parse(&[0x60, 0x04, 0x0b, 0x07]),
+            Err(DfuError::Target {
+                opcode: opcode::OBJECT_EXECUTE,
+                result: RESULT_EXT_ERROR,
+                extended: Some(0x07)
+            })
+        ));
+        assert!(matches!(
+            Response::parse(&[0x60, 0x03, 0x01, 0x00]),
+            Err(DfuError::Malformed { len: 4 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn upload() {
+        let (host, device) = tokio::io::duplex(4096);
+        let target = tokio::spawn(Target::new(device).run());
+        let init_packet = image(140);
+        let firmware = image(5000);
+
+        let mut client = DfuClient::new(Framed::new(host, SlipCodec::new()));
+        assert_eq!(
+            Response::Pong { id: 7 },
+            client.request(&Request::Ping { id: 7 }).await.unwrap()
+        );
+        client.upload(&init_packet, &firmware).await.unwrap();
+        assert_eq!(DfuState::Complete, client.state());
+
+        drop(client);
+        let target = target.await.unwrap();
+        assert_eq!(init_packet, target.executed[0]);
+        assert_eq!(firmware, target.executed[1]);
+    }
+
+    #[tokio::test]
+    async fn upload_detects_corruption() {
+        let (host, device) = tokio::io::duplex(4096);
+        let mut target = Target::new(device);
+        // the init packet takes 5 writes, so corrupt the first data object
+        target.corrupt_write = Some(8);
+        tokio::spawn(target.run());
+
+        let mut client = DfuClient::new(Framed::new(host, SlipCodec::new()));
+        let err = client.upload(&image(140), &image(5000)).await.unwrap_err();
+        assert!(matches!(
+            err,
+            DfuError::Verification {
+                expected: 1024,
+                received: 1024,
+                ..
+            }
+        ));
+        assert_eq!(DfuState::Firmware { offset: 0 }, client.state());
+    }
+
+    #[tokio::test]
+    async fn upload_rejected() {
+        let (host, device) = tokio::io::duplex(4096);
+        let mut target = Target::new(device);
+        target.max_create = 512;
+        tokio::spawn(target.run());
+
+        let mut client = DfuClient::new(Framed::new(host, SlipCodec::new()));
+        let err = client.upload(&image(140), &image(5000)).await.unwrap_err();
+        assert!(matches!(
+            err,
+            DfuError::Target {
+                opcode: opcode::OBJECT_CREATE,
+                result: 0x04,
+                extended: None
+            }
+        ));
+        assert_eq!(DfuState::Firmware { offset: 0 }, client.state());
+    }
+}
@@ -0,0 +1,295 @@
+use crate::{SlipDecoder, SlipError, MAX_PACKET_SIZE};
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+
+/// Buffered reader that yields decoded SLIP frames
+///
+/// `SlipReader` wraps any `std::io::Read` source in a `BufReader` and
+/// decodes frames from it with [`read_frame`] or by iterating over it.
+///
+/// [`read_frame`]: SlipReader::read_frame
+#[derive(Debug)]
+pub struct SlipReader<R> {
+    inner: BufReader<R>,
+    decoder: SlipDecoder,
+    /// part of a frame decoded before the source would block
+    partial: Vec<u8>,
+}
+
+impl<R: Read> SlipReader<R> {
+    /// Creates a new reader with a default buffer capacity that rejects
+    /// frames longer than [`MAX_PACKET_SIZE`]
+    pub fn new(inner: R) -> Self {
+        Self::with_decoder(SlipDecoder::with_max_len(MAX_PACKET_SIZE), inner)
+    }
+
+    /// Creates a new reader with the given buffer capacity that rejects
+    /// frames longer than [`MAX_PACKET_SIZE`]
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self {
+            inner: BufReader::with_capacity(capacity, inner),
+            decoder: SlipDecoder::with_max_len(MAX_PACKET_SIZE),
+            partial: Vec::new(),
+        }
+    }
+
+    /// Creates a new reader that decodes frames with `decoder`, e.g. one
+    /// configured through [`SlipDecoder::builder`].
+    ///
+    /// A frame is buffered up to the decoder's maximum length; a decoder
+    /// without one buffers a source that never sends `END` without bound.
+    pub fn with_decoder(decoder: SlipDecoder, inner: R) -> Self {
+        Self {
+            inner: BufReader::new(inner),
+            decoder,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Returns a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+
+    /// Returns a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+
+    /// Unwraps this `SlipReader`, returning the underlying reader.
+    ///
+    /// Any buffered data that has not been decoded yet is lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+
+    /// Reads the next frame and appends it to `buf`.
+    ///
+    /// Returns the length of the frame, or `None` if the source reached end
+    /// of file between frames. Reaching end of file in the middle of a frame
+    /// returns [`SlipError::Truncated`]. On error nothing is appended to
+    /// `buf`.
+    ///
+    /// A non-blocking source that has no data returns
+    /// [`SlipError::WouldBlock`]; the partial frame is kept and completed by
+    /// the next call. After any other I/O error the rest of the frame is
+    /// skipped.
+    pub fn read_frame(&mut self, buf: &mut Vec<u8>) -> Result<Option<usize>, SlipError> {
+        let start = buf.len();
+
+        buf.append(&mut self.partial);
+
+        let result = self.read_frame_into(buf, start);
+
+        match result {
+            Err(SlipError::WouldBlock { .. }) => self.partial.extend(buf.drain(start..)),
+            Err(_) => buf.truncate(start),
+            Ok(_) => {}
+        }
+
+        result
+    }
+
+    fn read_frame_into(
+        &mut self,
+        buf: &mut Vec<u8>,
+        start: usize,
+    ) -> Result<Option<usize>, SlipError> {
+        loop {
+            let src = match self.inner.fill_buf() {
+                Ok(src) => src,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                    return Err(SlipError::WouldBlock {
+                        len: buf.len() - start,
+                    });
+                }
+                Err(error) => {
+                    self.decoder.drop_frame();
+
+                    return Err(error.into());
+                }
+            };
+
+            if src.is_empty() {
+                if self.decoder.in_frame() {
+                    let len = self.decoder.partial_len();
+
+                    self.decoder.reset();
+
+                    return Err(SlipError::Truncated { len });
+                }
+
+                return Ok(None);
+            }
+
+            // each encoded byte decodes to at most one byte, except that
+            // `EscapePolicy::PassBoth` may yield two for an escape pending
+            // from the previous read
+            let len = buf.len();
+            buf.resize(len + src.len() + 1, 0u8);
+
+            let decoded = self.decoder.decode_slice(src, &mut buf[len..]);
+
+            buf.truncate(len + decoded.produced);
+            self.inner.consume(decoded.consumed);
+
+            if decoded.frame?.is_some() {
+                return Ok(Some(buf.len() - start));
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for SlipReader<R> {
+    type Item = Result<Vec<u8>, SlipError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = Vec::new();
+
+        match self.read_frame(&mut buf) {
+            Ok(Some(_)) => Some(Ok(buf)),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{Script, ScriptedReader};
+    use crate::EscapePolicy;
+
+    #[test]
+    fn read_frames() {
+        const INPUT: [u8; 13] = [
+            0xc0, 0x01, 0xdb, 0xdc, 0x03, 0xc0, 0xc0, 0x05, 0x06, 0xdb, 0xdd, 0x08, 0xc0,
+        ];
+
+        // a tiny buffer forces frames to span several reads
+        let reader = SlipReader::with_capacity(2, INPUT.as_ref());
+        let frames: Vec<Vec<u8>> = reader.map(Result::unwrap).collect();
+        assert_eq!(
+            vec![vec![0x01, 0xc0, 0x03], vec![0x05, 0x06, 0xdb, 0x08]],
+            frames
+        );
+    }
+
+    #[test]
+    fn read_frame_appends() {
+        const INPUT: [u8; 4] = [0xc0, 0x01, 0x02, 0xc0];
+
+        let mut reader = SlipReader::new(INPUT.as_ref());
+        let mut buf = vec![0xff];
+        assert_eq!(Some(2), reader.read_frame(&mut buf).unwrap());
+        assert_eq!(&[0xff, 0x01, 0x02], buf.as_slice());
+        assert_eq!(None, reader.read_frame(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn eof_mid_frame() {
+        const INPUT: [u8; 6] = [0xc0, 0x01, 0xc0, 0xc0, 0x02, 0x03];
+
+        let mut reader = SlipReader::new(INPUT.as_ref());
+        assert_eq!(vec![0x01], reader.next().unwrap().unwrap());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(SlipError::Truncated { len: 2 }))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn framing_error_continues() {
+        const INPUT: [u8; 8] = [0xc0, 0x01, 0xdb, 0x02, 0xc0, 0x03, 0x04, 0xc0];
+
+        let mut reader = SlipReader::new(INPUT.as_ref());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(SlipError::InvalidEscape { byte: 0x02, .. }))
+        ));
+        assert_eq!(vec![0x03, 0x04], reader.next().unwrap().unwrap());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn would_block_mid_frame() {
+        let source = ScriptedReader(
+            vec![
+                Script::Data(&[0xc0, 0x01, 0xdb]),
+                Script::Fail(ErrorKind::WouldBlock),
+                Script::Data(&[0xdc, 0x03, 0xc0]),
+            ]
+            .into(),
+        );
+
+        let mut reader = SlipReader::new(source);
+        let mut buf = vec![0xff];
+        assert!(matches!(
+            reader.read_frame(&mut buf),
+            Err(SlipError::WouldBlock { len: 1 })
+        ));
+        assert_eq!(&[0xff], buf.as_slice());
+        assert_eq!(Some(3), reader.read_frame(&mut buf).unwrap());
+        assert_eq!(&[0xff, 0x01, 0xc0, 0x03], buf.as_slice());
+        assert_eq!(None, reader.read_frame(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn io_error_mid_frame() {
+        let source = ScriptedReader(
+            vec![
+                Script::Data(&[0xc0, 0x01, 0x02]),
+                Script::Fail(ErrorKind::BrokenPipe),
+                Script::Data(&[0x03, 0xc0, 0x04, 0xc0]),
+            ]
+            .into(),
+        );
+
+        let mut reader = SlipReader::new(source);
+        assert!(matches!(reader.next(), Some(Err(SlipError::ReadError(_)))));
+        // the rest of the interrupted frame is not mistaken for a frame
+        assert_eq!(vec![0x04], reader.next().unwrap().unwrap());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn with_decoder() {
+        const INPUT: [u8; 5] = [0x01, 0xdb, 0x02, 0xc0, 0xc0];
+
+        let decoder = SlipDecoder::builder()
+            .escape_policy(EscapePolicy::PassBoth)
+            .empty_frames(true)
+            .build();
+        // the invalid escape sequence spans two reads
+        let mut reader = SlipReader::with_decoder(
+            decoder,
+            ScriptedReader(vec![Script::Data(&INPUT[..2]), Script::Data(&INPUT[2..])].into()),
+        );
+        assert_eq!(vec![0x01, 0xdb, 0x02], reader.next().unwrap().unwrap());
+        assert_eq!(Vec::<u8>::new(), reader.next().unwrap().unwrap());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn max_len_bounds_buffer() {
+        // a source that never sends END is not buffered without bound
+        let input = vec![0x55u8; 1 << 16];
+
+        let mut reader = SlipReader::new(input.as_slice());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(SlipError::OversizedPacket {
+                limit: MAX_PACKET_SIZE
+            }))
+        ));
+        assert!(reader.next().is_none());
+
+        let decoder = SlipDecoder::with_max_len(16);
+        let mut reader = SlipReader::with_decoder(decoder, input.as_slice());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(SlipError::OversizedPacket { limit: 16 }))
+        ));
+    }
+}
@@ -0,0 +1,49 @@
+/// Link quality counters maintained by [`SlipDecoder`]
+///
+/// All counters start at zero and only ever increase until they are cleared
+/// with [`SlipDecoder::reset_stats`].
+///
+/// [`SlipDecoder`]: crate::SlipDecoder
+/// [`SlipDecoder::reset_stats`]: crate::SlipDecoder::reset_stats
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SlipStats {
+    /// Number of complete, non-empty frames decoded
+    pub frames_decoded: u64,
+    /// Number of encoded bytes consumed
+    pub bytes_in: u64,
+    /// Number of decoded bytes delivered in complete frames
+    pub bytes_out: u64,
+    /// Number of empty frames received (skipped unless empty frames are
+    /// enabled), i.e. `END` tokens with no data since the previous `END`
+    pub empty_frames: u64,
+    /// Number of invalid escape sequences seen, whatever the escape policy
+    pub framing_errors: u64,
+    /// Number of frames dropped for exceeding the maximum frame length
+    pub oversize_drops: u64,
+    /// Number of encoded bytes discarded while waiting for the `END` token
+    /// that ends a dropped frame
+    pub bytes_discarded: u64,
+}
+
+#[cfg(feature = "metrics")]
+impl SlipStats {
+    /// Publishes the counters to the installed [`metrics`] recorder.
+    ///
+    /// Each counter is reported as an absolute value under the name
+    /// `slip_<field>`, e.g. `slip_frames_decoded`, with the given labels.
+    pub fn record_metrics(&self, labels: &[(&'static str, &'static str)]) {
+        let counters = [
+            ("slip_frames_decoded", self.frames_decoded),
+            ("slip_bytes_in", self.bytes_in),
+            ("slip_bytes_out", self.bytes_out),
+            ("slip_empty_frames", self.empty_frames),
+            ("slip_framing_errors", self.framing_errors),
+            ("slip_oversize_drops", self.oversize_drops),
+            ("slip_bytes_discarded", self.bytes_discarded),
+        ];
+
+        for (name, value) in counters {
+            metrics::counter!(name, labels).absolute(value);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! Helpers shared by the unit tests
+
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+
+/// Sink that records how many times it was flushed
+#[derive(Default)]
+pub(crate) struct FlushCounter {
+    pub(crate) data: Vec<u8>,
+    pub(crate) flushes: usize,
+}
+
+impl Write for FlushCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+/// Step of a [`ScriptedReader`]
+pub(crate) enum Script {
+    Data(&'static [u8]),
+    Fail(ErrorKind),
+}
+
+/// Reader that serves data and injects errors in a scripted order, then
+/// reports the end of the data
+pub(crate) struct ScriptedReader(pub(crate) VecDeque<Script>);
+
+impl Read for ScriptedReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.pop_front() {
+            Some(Script::Data(data)) => {
+                let len = data.len().min(buf.len());
+
+                buf[..len].copy_from_slice(&data[..len]);
+                if len < data.len() {
+                    self.0.push_front(Script::Data(&data[len..]));
+                }
+
+                Ok(len)
+            }
+            Some(Script::Fail(kind)) => Err(kind.into()),
+            None => Ok(0),
+        }
+    }
+}
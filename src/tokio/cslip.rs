@@ -0,0 +1,118 @@
+use super::SlipCodec;
+use crate::cslip::{Compressor, Decompressor};
+use crate::framing::{CslipLayer, FrameLayer};
+use crate::SlipError;
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// SLIP codec with RFC 1144 TCP/IP header compression
+///
+/// Encoded IP packets are compressed and decoded frames are restored to
+/// full IP packets. Frames that cannot be decompressed are discarded rather
+/// than ending the stream, as TCP retransmissions resynchronise the link.
+#[derive(Debug)]
+pub struct CslipCodec {
+    codec: SlipCodec,
+    layer: CslipLayer,
+}
+
+impl CslipCodec {
+    pub fn new() -> Self {
+        Self::with_codec(
+            SlipCodec::new(),
+            Compressor::default(),
+            Decompressor::default(),
+        )
+    }
+
+    /// Layers header compression over a configured SLIP codec
+    pub fn with_codec(
+        codec: SlipCodec,
+        compressor: Compressor,
+        decompressor: Decompressor,
+    ) -> Self {
+        Self {
+            codec,
+            layer: CslipLayer::new(compressor, decompressor),
+        }
+    }
+
+    pub fn get_ref(&self) -> &SlipCodec {
+        &self.codec
+    }
+
+    pub fn into_inner(self) -> SlipCodec {
+        self.codec
+    }
+}
+
+impl Default for CslipCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for CslipCodec {
+    type Item = Bytes;
+    type Error = SlipError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (decoder, _) = self.codec.engine_mut();
+
+        self.layer
+            .decode(decoder, src)
+            .map(|payload| payload.map(BytesMut::freeze))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (decoder, _) = self.codec.engine_mut();
+
+        self.layer
+            .decode_eof(decoder, src)
+            .map(|payload| payload.map(BytesMut::freeze))
+    }
+}
+
+impl<T: AsRef<[u8]>> Encoder<T> for CslipCodec {
+    type Error = SlipError;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let (_, encoder) = self.codec.engine_mut();
+
+        self.layer
+            .encode(item.as_ref(), encoder, dst)
+            .map_err(SlipError::ReadError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cslip::tests::session;
+
+    #[test]
+    fn round_trip() {
+        let mut codec = CslipCodec::new();
+        let mut buf = BytesMut::new();
+
+        for packet in session() {
+            codec.encode(Bytes::from(packet), &mut buf).unwrap();
+        }
+
+        for packet in session() {
+            assert_eq!(&packet[..], &codec.decode(&mut buf).unwrap().unwrap()[..]);
+        }
+
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn discard_undecompressable() {
+        let mut codec = CslipCodec::new();
+        let mut buf = BytesMut::from(&[0xc0, 0x20, 0x00, 0xc0][..]);
+        let packet = session().remove(0);
+
+        codec.encode(Bytes::from(packet.clone()), &mut buf).unwrap();
+        assert_eq!(&packet[..], &codec.decode(&mut buf).unwrap().unwrap()[..]);
+    }
+}
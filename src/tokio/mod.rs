@@ -1,8 +1,24 @@
+#[cfg(feature = "checksum")]
+mod checksum;
+#[cfg(feature = "checksum")]
+pub use checksum::ChecksumCodec;
+
 mod codec;
-pub use codec::SlipCodec;
+pub use codec::{SlipCodec, SlipCodecBuilder};
+pub use crate::framing::SlipCodecConfig;
+
+#[cfg(feature = "cslip")]
+mod cslip;
+#[cfg(feature = "cslip")]
+pub use cslip::CslipCodec;
 
 mod decoder;
 pub use decoder::SlipDecoder;
 
 mod encoder;
 pub use encoder::SlipEncoder;
+
+#[cfg(feature = "reliable")]
+mod reliable;
+#[cfg(feature = "reliable")]
+pub use reliable::{ReliableError, ReliableFramed, ReliableFramedBuilder};
@@ -0,0 +1,521 @@
+use super::SlipCodec;
+use crate::checksum::{Checksum, Crc16Ccitt};
+use crate::SlipError;
+use ::tokio::io::{AsyncRead, AsyncWrite};
+use ::tokio::time::{Instant, Sleep};
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio_util::codec::Framed;
+
+/// Header kind of a frame carrying a payload
+const DATA: u8 = 0x01;
+
+/// Header kind of a frame acknowledging a payload
+const ACK: u8 = 0x02;
+
+/// Kind, sequence number and CRC-16 added to every payload
+const OVERHEAD: usize = 2 + Crc16Ccitt::LEN;
+
+/// Reliable transport error type
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReliableError {
+    /// The underlying codec or I/O object failed
+    Slip(SlipError),
+    /// A payload was not acknowledged after `retries` retransmissions
+    Timeout { retries: usize },
+    /// The underlying stream ended while a payload was unacknowledged
+    Closed,
+}
+
+impl core::fmt::Display for ReliableError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ReliableError::Slip(err) => write!(f, "{}", err),
+            ReliableError::Timeout { retries } => {
+                write!(f, "payload not acknowledged after {} retries", retries)
+            }
+            ReliableError::Closed => write!(f, "stream closed before acknowledgement"),
+        }
+    }
+}
+
+impl std::error::Error for ReliableError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReliableError::Slip(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SlipError> for ReliableError {
+    fn from(err: SlipError) -> Self {
+        ReliableError::Slip(err)
+    }
+}
+
+/// Payload waiting for its acknowledgement
+#[derive(Debug)]
+struct Pending {
+    seq: u8,
+    frame: Bytes,
+    retries: usize,
+    sent: bool,
+}
+
+/// Stop-and-wait ARQ transport over a SLIP framed I/O object
+///
+/// Every payload is sent in a frame together with a kind, an 8-bit
+/// sequence number and a CRC-16/CCITT checksum. The peer acknowledges each
+/// intact frame; unacknowledged frames are retransmitted after a timeout
+/// until a retry limit is reached, and retransmitted payloads that were
+/// already delivered are suppressed. A payload given up on is abandoned and
+/// the transport carries on with the next one. Corrupted frames are dropped
+/// silently and recovered by retransmission.
+///
+/// Payloads are written through the [`Sink`] implementation, which accepts
+/// one payload at a time and completes a flush once it is acknowledged.
+/// Received payloads are read through the [`Stream`] implementation. Both
+/// directions share the underlying I/O object, so acknowledgements are only
+/// processed while the transport is polled.
+#[derive(Debug)]
+pub struct ReliableFramed<T> {
+    inner: Framed<T, SlipCodec>,
+    timeout: Duration,
+    max_retries: usize,
+    next_seq: u8,
+    /// Sequence number of the last delivered payload
+    delivered_seq: Option<u8>,
+    pending: Option<Pending>,
+    timer: Pin<Box<Sleep>>,
+    acks: VecDeque<u8>,
+    received: VecDeque<Bytes>,
+    unflushed: bool,
+    closed: bool,
+}
+
+/// Builder for [`ReliableFramed`] configuration
+#[derive(Debug)]
+pub struct ReliableFramedBuilder {
+    timeout: Duration,
+    max_retries: usize,
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> ReliableFramed<T> {
+    /// Creates a transport with a 1 second timeout and 3 retries
+    pub fn new(inner: Framed<T, SlipCodec>) -> Self {
+        ReliableFramedBuilder::new().build(inner)
+    }
+
+    pub fn get_ref(&self) -> &Framed<T, SlipCodec> {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> Framed<T, SlipCodec> {
+        self.inner
+    }
+
+    /// Makes as much progress as possible without blocking: processes
+    /// received frames, retransmits on timeout and writes pending frames.
+    fn drive(&mut self, cx: &mut Context<'_>) -> Result<(), ReliableError> {
+        loop {
+            let mut progress = false;
+
+            while !self.closed {
+                match Pin::new(&mut self.inner).poll_next(cx) {
+                    Poll::Ready(Some(Ok(frame))) => self.receive(frame),
+                    Poll::Ready(Some(Err(err))) => return Err(err.into()),
+                    Poll::Ready(None) => self.closed = true,
+                    Poll::Pending => break,
+                }
+            }
+
+            if let Some(pending) = self.pending.as_mut() {
+                if pending.sent && self.timer.as_mut().poll(cx).is_ready() {
+                    if pending.retries == self.max_retries {
+                        let (seq, retries) = (pending.seq, pending.retries);
+
+                        // give up on the payload so the transport stays usable
+                        self.pending = None;
+                        self.next_seq = seq.wrapping_add(1);
+
+                        return Err(ReliableError::Timeout { retries });
+                    }
+
+                    pending.retries += 1;
+                    pending.sent = false;
+                }
+            }
+
+            let unsent = self.pending.as_ref().is_some_and(|pending| !pending.sent);
+
+            if !self.acks.is_empty() || unsent {
+                if let Poll::Ready(result) =
+                    Sink::<Bytes>::poll_ready(Pin::new(&mut self.inner), cx)
+                {
+                    result?;
+
+                    if let Some(seq) = self.acks.pop_front() {
+                        Pin::new(&mut self.inner).start_send(frame(ACK, seq, &[]))?;
+                    } else if let Some(pending) = self.pending.as_mut() {
+                        Pin::new(&mut self.inner).start_send(pending.frame.clone())?;
+                        pending.sent = true;
+                        self.timer.as_mut().reset(Instant::now() + self.timeout);
+                    }
+
+                    self.unflushed = true;
+                    progress = true;
+                }
+            }
+
+            if self.unflushed {
+                if let Poll::Ready(result) =
+                    Sink::<Bytes>::poll_flush(Pin::new(&mut self.inner), cx)
+                {
+                    result?;
+                    self.unflushed = false;
+                }
+            }
+
+            if !progress {
+                return Ok(());
+            }
+        }
+    }
+
+    fn receive(&mut self, frame: Bytes) {
+        if frame.len() < OVERHEAD || Crc16Ccitt.verify(&frame).is_err() {
+            return;
+        }
+
+        let (kind, seq) = (frame[0], frame[1]);
+
+        match kind {
+            DATA => {
+                self.acks.push_back(seq);
+
+                // a stop-and-wait sender only ever repeats the last payload,
+                // and moves on to the next sequence number after giving up
+                if self.delivered_seq != Some(seq) {
+                    self.delivered_seq = Some(seq);
+                    self.received
+                        .push_back(frame.slice(2..frame.len() - Crc16Ccitt::LEN));
+                }
+            }
+            ACK if self
+                .pending
+                .as_ref()
+                .is_some_and(|pending| pending.seq == seq) =>
+            {
+                self.pending = None;
+                self.next_seq = seq.wrapping_add(1);
+            }
+            _ => {}
+        }
+    }
+
+    /// Waits until the pending payload is acknowledged
+    fn poll_acknowledged(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), ReliableError>> {
+        self.drive(cx)?;
+
+        if self.pending.is_none() {
+            Poll::Ready(Ok(()))
+        } else if self.closed {
+            Poll::Ready(Err(ReliableError::Closed))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Builds a frame of the given kind around `payload`
+fn frame(kind: u8, seq: u8, payload: &[u8]) -> Bytes {
+    let mut frame = BytesMut::with_capacity(payload.len() + OVERHEAD);
+
+    frame.put_u8(kind);
+    frame.put_u8(seq);
+    frame.extend_from_slice(payload);
+
+    let trailer = Crc16Ccitt.trailer(&frame);
+
+    frame.extend_from_slice(&trailer[..Crc16Ccitt::LEN]);
+    frame.freeze()
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Sink<Bytes> for ReliableFramed<T> {
+    type Error = ReliableError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_acknowledged(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        let seq = this.next_seq;
+
+        this.pending = Some(Pending {
+            seq,
+            frame: frame(DATA, seq, &item),
+            retries: 0usize,
+            sent: false,
+        });
+
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+
+        match this.poll_acknowledged(cx) {
+            Poll::Ready(Ok(())) if this.unflushed || !this.acks.is_empty() => Poll::Pending,
+            poll => poll,
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+
+        match Pin::new(&mut *this).poll_flush(cx) {
+            Poll::Ready(Ok(())) => {
+                Sink::<Bytes>::poll_close(Pin::new(&mut this.inner), cx).map_err(Into::into)
+            }
+            poll => poll,
+        }
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Stream for ReliableFramed<T> {
+    type Item = Result<Bytes, ReliableError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Err(err) = this.drive(cx) {
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        match this.received.pop_front() {
+            Some(payload) => Poll::Ready(Some(Ok(payload))),
+            None if this.closed => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl ReliableFramedBuilder {
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_secs(1),
+            max_retries: 3usize,
+        }
+    }
+
+    /// Time to wait for an acknowledgement before retransmitting
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Number of retransmissions before giving up with
+    /// [`ReliableError::Timeout`]
+    pub fn max_retries(self, max_retries: usize) -> Self {
+        Self {
+            max_retries,
+            ..self
+        }
+    }
+
+    pub fn build<T>(self, inner: Framed<T, SlipCodec>) -> ReliableFramed<T> {
+        ReliableFramed {
+            inner,
+            timeout: self.timeout,
+            max_retries: self.max_retries,
+            next_seq: 0u8,
+            delivered_seq: None,
+            pending: None,
+            timer: Box::pin(::tokio::time::sleep(Duration::ZERO)),
+            acks: VecDeque::new(),
+            received: VecDeque::new(),
+            unflushed: false,
+            closed: false,
+        }
+    }
+}
+
+impl Default for ReliableFramedBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use std::io;
+    use tokio::io::{DuplexStream, ReadBuf};
+
+    /// Drops every `nth` byte written
+    struct Lossy {
+        inner: DuplexStream,
+        nth: usize,
+        count: usize,
+    }
+
+    impl AsyncRead for Lossy {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for Lossy {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let this = &mut *self;
+            let skip = this.nth - 1 - this.count % this.nth;
+
+            if skip == 0 {
+                this.count += 1;
+                return Poll::Ready(Ok(1));
+            }
+
+            let run = &buf[..skip.min(buf.len())];
+
+            match Pin::new(&mut this.inner).poll_write(cx, run) {
+                Poll::Ready(Ok(len)) => {
+                    this.count += len;
+                    Poll::Ready(Ok(len))
+                }
+                poll => poll,
+            }
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    fn lossy_pair(nth: usize) -> (ReliableFramed<Lossy>, ReliableFramed<Lossy>) {
+        let (a, b) = tokio::io::duplex(4096);
+        let builder = || {
+            ReliableFramedBuilder::new()
+                .timeout(Duration::from_millis(20))
+                .max_retries(20)
+        };
+        let a = Lossy {
+            inner: a,
+            nth,
+            count: 0usize,
+        };
+        let b = Lossy {
+            inner: b,
+            nth,
+            count: 0usize,
+        };
+
+        (
+            builder().build(Framed::new(a, SlipCodec::new())),
+            builder().build(Framed::new(b, SlipCodec::new())),
+        )
+    }
+
+    fn payload(index: usize) -> Bytes {
+        Bytes::from(vec![index as u8; 1 + index % 13])
+    }
+
+    #[tokio::test]
+    async fn deliver_in_order_despite_loss() {
+        const COUNT: usize = 50;
+
+        let (mut a, mut b) = lossy_pair(37);
+
+        let receiver = tokio::spawn(async move {
+            let mut payloads = Vec::new();
+
+            while payloads.len() < COUNT {
+                payloads.push(b.next().await.unwrap().unwrap());
+            }
+
+            // keep acknowledging retransmissions until the sender is done
+            let _ = tokio::time::timeout(Duration::from_millis(200), b.next()).await;
+
+            payloads
+        });
+
+        for index in 0..COUNT {
+            a.send(payload(index)).await.unwrap();
+        }
+
+        let payloads = receiver.await.unwrap();
+        assert_eq!((0..COUNT).map(payload).collect::<Vec<_>>(), payloads);
+    }
+
+    #[tokio::test]
+    async fn bidirectional() {
+        const COUNT: usize = 20;
+
+        let (mut a, mut b) = lossy_pair(23);
+
+        let peer = tokio::spawn(async move {
+            for index in 0..COUNT {
+                let received = b.next().await.unwrap().unwrap();
+                assert_eq!(payload(index), received);
+                b.send(payload(index + 1)).await.unwrap();
+            }
+
+            let _ = tokio::time::timeout(Duration::from_millis(200), b.next()).await;
+        });
+
+        for index in 0..COUNT {
+            a.send(payload(index)).await.unwrap();
+            assert_eq!(payload(index + 1), a.next().await.unwrap().unwrap());
+        }
+
+        let _ = tokio::time::timeout(Duration::from_millis(200), a.next()).await;
+        peer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn give_up_without_acknowledgement() {
+        let (a, mut b) = tokio::io::duplex(4096);
+        let mut a = ReliableFramedBuilder::new()
+            .timeout(Duration::from_millis(5))
+            .max_retries(2)
+            .build(Framed::new(a, SlipCodec::new()));
+
+        let err = a.send(Bytes::from_static(b"hello")).await.unwrap_err();
+        assert!(matches!(err, ReliableError::Timeout { retries: 2 }));
+
+        // the original transmission and both retries reached the peer
+        let mut buf = vec![0u8; 1024];
+        let len = tokio::io::AsyncReadExt::read(&mut b, &mut buf)
+            .await
+            .unwrap();
+        let frames = buf[..len].iter().filter(|value| **value == 0xc0).count();
+        assert_eq!(6, frames);
+
+        // the transport remains usable with a peer that never saw the
+        // abandoned payload
+        let mut b = ReliableFramed::new(Framed::new(b, SlipCodec::new()));
+        let (sent, received) = tokio::join!(a.send(Bytes::from_static(b"world")), b.next());
+        sent.unwrap();
+        assert_eq!(&b"world"[..], &received.unwrap().unwrap()[..]);
+    }
+}
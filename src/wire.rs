@@ -0,0 +1,45 @@
+//! Field accessors shared by the protocol modules
+
+/// Reads the big endian `u16` at `offset`
+#[cfg(any(feature = "cslip", feature = "ip"))]
+pub(crate) fn read_u16_be(buf: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([buf[offset], buf[offset + 1]])
+}
+
+/// Reads the big endian `u32` at `offset`
+#[cfg(feature = "cslip")]
+pub(crate) fn read_u32_be(buf: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    ])
+}
+
+/// Reads the little endian `u32` at `offset`
+#[cfg(any(feature = "esp", feature = "nrf_dfu"))]
+pub(crate) fn read_u32_le(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    ])
+}
+
+/// Returns the ones' complement of the ones' complement sum of the 16-bit
+/// words of `header`, which is zero for a header with a valid checksum
+#[cfg(any(feature = "cslip", feature = "ip"))]
+pub(crate) fn header_checksum(header: &[u8]) -> u16 {
+    let mut sum = header
+        .chunks(2)
+        .map(|word| u32::from(read_u16_be(word, 0)))
+        .sum::<u32>();
+
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    !(sum as u16)
+}
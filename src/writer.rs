@@ -0,0 +1,226 @@
+use crate::{FlushPolicy, SlipEncoder};
+use std::io::Write;
+
+/// `std::io::Write` adapter that encodes SLIP frames
+///
+/// Bytes written to a `SlipWriter` are escaped and forwarded to the
+/// underlying writer as part of the current frame, which is terminated by
+/// [`end_frame`]. This allows packets to be streamed in pieces without
+/// buffering them entirely in memory. Alternatively, automatic framing makes
+/// every `write` call a complete frame (see [`SlipWriterBuilder`]).
+///
+/// [`end_frame`]: SlipWriter::end_frame
+#[derive(Debug)]
+pub struct SlipWriter<W: Write> {
+    inner: W,
+    encoder: SlipEncoder,
+    auto_frame: bool,
+}
+
+/// Builder for [`SlipWriter`] configuration
+#[derive(Debug)]
+pub struct SlipWriterBuilder {
+    begin_with_end: bool,
+    auto_frame: bool,
+    flush_policy: FlushPolicy,
+}
+
+impl<W: Write> SlipWriter<W> {
+    /// Creates a new writer that begins frames with an `END` token and
+    /// flushes after each frame
+    pub fn new(inner: W) -> Self {
+        SlipWriterBuilder::new().build(inner)
+    }
+
+    /// Returns a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps this `SlipWriter`, returning the underlying writer.
+    ///
+    /// A frame that has not been ended is left incomplete.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Terminates the current frame with an `END` token and flushes the
+    /// underlying writer according to the flush policy.
+    ///
+    /// Ending a frame without writing any data emits an empty frame.
+    pub fn end_frame(&mut self) -> std::io::Result<()> {
+        let mut chunk = [0u8; 3];
+        let len = self
+            .encoder
+            .finish_slice(&mut chunk)
+            .expect("chunk holds a frame terminator");
+
+        self.inner.write_all(&chunk[..len])?;
+        self.encoder.frames_written(1, &mut self.inner)
+    }
+
+    /// Writes `buf` as a complete frame, regardless of automatic framing
+    pub fn write_frame(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.write_escaped(buf)?;
+        self.end_frame()
+    }
+
+    fn write_escaped(&mut self, mut buf: &[u8]) -> std::io::Result<()> {
+        let mut chunk = [0u8; 1024];
+
+        loop {
+            let encoded = self.encoder.encode_slice(buf, &mut chunk);
+
+            self.inner.write_all(&chunk[..encoded.produced])?;
+            buf = &buf[encoded.consumed..];
+
+            // a partially filled chunk means nothing is left pending
+            if buf.is_empty() && encoded.produced < chunk.len() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for SlipWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.auto_frame {
+            self.write_frame(buf)?;
+        } else {
+            self.write_escaped(buf)?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.encoder.flush(&mut self.inner)
+    }
+}
+
+impl SlipWriterBuilder {
+    /// Creates a builder with the same defaults as [`SlipWriter::new`]
+    pub fn new() -> Self {
+        Self {
+            begin_with_end: true,
+            auto_frame: false,
+            flush_policy: FlushPolicy::PerFrame,
+        }
+    }
+
+    pub fn begin_with_end(self, begin_with_end: bool) -> Self {
+        Self {
+            begin_with_end,
+            ..self
+        }
+    }
+
+    /// Terminate a frame after every `write` call
+    pub fn auto_frame(self, auto_frame: bool) -> Self {
+        Self { auto_frame, ..self }
+    }
+
+    pub fn flush_policy(self, flush_policy: FlushPolicy) -> Self {
+        Self {
+            flush_policy,
+            ..self
+        }
+    }
+
+    pub fn build<W: Write>(self, inner: W) -> SlipWriter<W> {
+        SlipWriter {
+            inner,
+            encoder: SlipEncoder::builder()
+                .begin_with_end(self.begin_with_end)
+                .flush_policy(self.flush_policy)
+                .build(),
+            auto_frame: self.auto_frame,
+        }
+    }
+}
+
+impl Default for SlipWriterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::FlushCounter;
+    use crate::{END, ESC, ESC_END, ESC_ESC};
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn streamed_frame() {
+        const EXPECTED: [u8; 8] = [0xc0, 0x01, ESC, ESC_END, ESC, ESC_ESC, 0x03, 0xc0];
+
+        let mut writer = SlipWriter::new(Vec::new());
+        writer.write_all(&[0x01, END]).unwrap();
+        writer.write_all(&[ESC]).unwrap();
+        writer.write_all(&[0x03]).unwrap();
+        writer.end_frame().unwrap();
+        assert_eq!(&EXPECTED, writer.get_ref().as_slice());
+    }
+
+    #[test]
+    fn large_streamed_frame() {
+        let input: Vec<u8> = (0..4096).map(|value| value as u8).collect();
+
+        let mut writer = SlipWriter::new(Vec::new());
+        for chunk in input.chunks(100) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.end_frame().unwrap();
+
+        let mut expected = Vec::new();
+        SlipEncoder::new(true)
+            .encode(&input, &mut expected)
+            .unwrap();
+        assert_eq!(expected, writer.into_inner());
+    }
+
+    #[test]
+    fn auto_frame() {
+        const EXPECTED: [u8; 7] = [0x01, 0xc0, 0x02, 0x03, 0xc0, 0xc0, 0xc0];
+
+        let mut writer = SlipWriterBuilder::new()
+            .begin_with_end(false)
+            .auto_frame(true)
+            .build(Vec::new());
+        writer.write_all(&[0x01]).unwrap();
+        writer.write_all(&[0x02, 0x03]).unwrap();
+        writer.end_frame().unwrap();
+        writer.write_frame(&[]).unwrap();
+        assert_eq!(&EXPECTED, writer.get_ref().as_slice());
+    }
+
+    #[test]
+    fn flush_policy() {
+        let mut writer = SlipWriter::new(FlushCounter::default());
+        writer.write_frame(&[0x01]).unwrap();
+        writer.write_frame(&[0x02]).unwrap();
+        assert_eq!(2, writer.get_ref().flushes);
+
+        let mut writer = SlipWriterBuilder::new()
+            .flush_policy(FlushPolicy::Never)
+            .build(FlushCounter::default());
+        writer.write_frame(&[0x01]).unwrap();
+        writer.write_frame(&[0x02]).unwrap();
+        assert_eq!(0, writer.get_ref().flushes);
+
+        let mut writer = SlipWriterBuilder::new()
+            .flush_policy(FlushPolicy::EveryNFrames(NonZeroUsize::new(3).unwrap()))
+            .build(FlushCounter::default());
+        for _ in 0..7 {
+            writer.write_frame(&[0x01]).unwrap();
+        }
+        assert_eq!(2, writer.get_ref().flushes);
+    }
+}